@@ -0,0 +1,65 @@
+use std::{collections::HashMap, str::FromStr};
+
+#[derive(Debug, Default)]
+pub(crate) struct Args {
+    mode: Option<String>,
+    options: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    pub(crate) fn parse(raw_args: impl Iterator<Item = String>) -> Self {
+        let mut args = Self::default();
+        let mut raw_args = raw_args.peekable();
+
+        while let Some(arg) = raw_args.next() {
+            match arg.strip_prefix("--") {
+                Some(option) => match option.split_once('=') {
+                    Some((name, value)) => {
+                        args.options.insert(name.to_owned(), Some(value.to_owned()));
+                    }
+                    None => {
+                        let value = raw_args.next_if(|next| !next.starts_with("--"));
+                        args.options.insert(option.to_owned(), value);
+                    }
+                },
+                None if args.mode.is_none() => args.mode = Some(arg),
+                None => panic!("unexpected argument: {arg}"),
+            }
+        }
+
+        args
+    }
+
+    pub(crate) fn mode(&self) -> Option<&str> {
+        self.mode.as_deref()
+    }
+
+    pub(crate) fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    pub(crate) fn input(&self, default: &str) -> String {
+        match self.value::<String>("input") {
+            Some(path) => std::fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("{path}: {e}"))
+                .trim_end_matches('\n')
+                .to_owned(),
+            None => default.to_owned(),
+        }
+    }
+
+    pub(crate) fn value<T>(&self, name: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Debug,
+    {
+        self.options
+            .get(name)
+            .and_then(|value| value.as_deref())
+            .map(|value| {
+                value
+                    .parse::<T>()
+                    .unwrap_or_else(|e| panic!("invalid value for --{name}: {e:?}"))
+            })
+    }
+}
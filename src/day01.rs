@@ -1,34 +1,231 @@
-fn part_1(input: &str) -> i32 {
+use itertools::iproduct;
+
+use crate::cli::Args;
+
+static HISTOGRAM_BUCKET_SIZE: i32 = 5000;
+static HISTOGRAM_WIDTH: usize = 50;
+static PERCENTILES: [usize; 5] = [10, 25, 50, 75, 90];
+
+fn backpacks(input: &str) -> Vec<Vec<i32>> {
     input
         .split("\n\n")
         .map(|backpack| {
             backpack
-                .split("\n")
+                .split('\n')
                 .map(|calorie| calorie.parse::<i32>().unwrap())
-                .fold(0, |sum, calorie| sum + calorie)
+                .collect::<Vec<_>>()
         })
-        .max()
-        .unwrap()
+        .collect()
+}
+
+fn checked_sum<'a>(calories: impl IntoIterator<Item = &'a i32>) -> i32 {
+    calories
+        .into_iter()
+        .try_fold(0i32, |sum, calorie| sum.checked_add(*calorie))
+        .expect("calorie total overflows i32")
+}
+
+fn total_calories(input: &str) -> Vec<i32> {
+    backpacks(input).iter().map(checked_sum).collect()
+}
+
+fn part_1(input: &str) -> i32 {
+    total_calories(input).into_iter().max().unwrap()
 }
 
 fn part_2(input: &str) -> i32 {
-    let mut total_calories = input
-        .split("\n\n")
-        .map(|backpack| {
-            backpack
-                .split("\n")
-                .map(|calorie| calorie.parse::<i32>().unwrap())
-                .fold(0, |sum, calorie| sum + calorie)
-        })
-        .collect::<Vec<_>>();
+    let mut total_calories = total_calories(input);
 
     total_calories.sort();
 
-    total_calories
-        .get(total_calories.len() - 3..total_calories.len())
-        .unwrap()
-        .into_iter()
-        .fold(0, |sum, calorie| sum + calorie)
+    checked_sum(&total_calories[total_calories.len() - 3..])
+}
+
+fn percentile(sorted: &[i32], p: usize) -> i32 {
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+
+    sorted[rank - 1]
+}
+
+fn median(sorted: &[i32]) -> f64 {
+    let mid = sorted.len() / 2;
+
+    match sorted.len() % 2 {
+        0 => (sorted[mid - 1] as f64 + sorted[mid] as f64) / 2.,
+        _ => sorted[mid] as f64,
+    }
+}
+
+fn report(input: &str, bucket_size: i32) {
+    let backpacks = backpacks(input);
+    let totals = backpacks.iter().map(checked_sum).collect::<Vec<_>>();
+
+    let mut sorted = totals.clone();
+    sorted.sort();
+
+    let grand_total = totals.iter().map(|total| *total as i64).sum::<i64>();
+    let mean = grand_total as f64 / totals.len() as f64;
+
+    println!("Day 01 report");
+    println!("\tElves: {}", backpacks.len());
+    println!("\tGrand total: {grand_total}");
+
+    println!("\tItems per elf:");
+    for (elf, backpack) in backpacks.iter().enumerate() {
+        println!(
+            "\t\tElf {:>4}: {:>2} items, {:>6} calories",
+            elf + 1,
+            backpack.len(),
+            totals[elf]
+        );
+    }
+
+    println!("\tMean: {mean:.2}");
+    println!("\tMedian: {:.1}", median(&sorted));
+    for p in PERCENTILES {
+        println!("\tP{p}: {}", percentile(&sorted, p));
+    }
+
+    // Bucket bounds go past i32 near its limits, so they are worked out in i64.
+    println!("\tHistogram (bucket size {bucket_size}):");
+    let bucket_size = bucket_size as i64;
+    let bucket = |total: &i32| (*total as i64).div_euclid(bucket_size);
+    let first_bucket = bucket(&sorted[0]);
+    let last_bucket = bucket(&sorted[sorted.len() - 1]);
+    let mut buckets = vec![0usize; (last_bucket - first_bucket + 1) as usize];
+    for total in &sorted {
+        buckets[(bucket(total) - first_bucket) as usize] += 1;
+    }
+    let tallest = buckets.iter().max().copied().unwrap_or(1);
+    for (idx, count) in buckets.iter().enumerate() {
+        let low = (first_bucket + idx as i64) * bucket_size;
+        let bar = "#".repeat(count * HISTOGRAM_WIDTH / tallest);
+        println!(
+            "\t\t{:>6}..{:<6} {:>4} {bar}",
+            low,
+            low + bucket_size,
+            count
+        );
+    }
+
+    let (elf, snack) = backpacks
+        .iter()
+        .enumerate()
+        .flat_map(|(elf, backpack)| backpack.iter().map(move |snack| (elf, *snack)))
+        .max_by_key(|(_, snack)| *snack)
+        .unwrap();
+    println!(
+        "\tLargest snack: {snack} calories, carried by elf {}",
+        elf + 1
+    );
+}
+
+struct SnackMove {
+    snack: i32,
+    from: usize,
+    to: usize,
+}
+
+fn spread(loads: &[i32]) -> i32 {
+    loads.iter().max().unwrap() - loads.iter().min().unwrap()
+}
+
+// Shifting `delta` calories from the heavier elf of a pair to the lighter one only
+// narrows the gap while 0 < delta < diff, and narrows it most when delta is closest
+// to diff / 2. Returns the best (gap after, given snack, taken snack) if any.
+fn best_transfer(
+    heavier: &[i32],
+    lighter: &[i32],
+    diff: i32,
+) -> Option<(i32, usize, Option<usize>)> {
+    let improves = |delta: i32| delta > 0 && delta < diff;
+    let gap = |delta: i32| (diff - 2 * delta).abs();
+
+    let best_single = heavier
+        .iter()
+        .enumerate()
+        .filter(|(_, snack)| improves(**snack))
+        .map(|(give, snack)| (gap(*snack), give, None));
+
+    let best_swap = iproduct!(heavier.iter().enumerate(), lighter.iter().enumerate())
+        .filter(|((_, give), (_, take))| improves(**give - **take))
+        .map(|((give_idx, give), (take_idx, take))| (gap(give - take), give_idx, Some(take_idx)));
+
+    best_single.chain(best_swap).min_by_key(|(gap, _, _)| *gap)
+}
+
+fn rebalance(backpacks: &[Vec<i32>]) -> (Vec<Vec<i32>>, Vec<SnackMove>) {
+    let mut backpacks = backpacks.to_vec();
+    let mut loads = backpacks.iter().map(checked_sum).collect::<Vec<_>>();
+    let mut moves = vec![];
+
+    // Every accepted transfer strictly lowers the sum of squared loads, so this ends.
+    loop {
+        let mut by_load = (0..loads.len()).collect::<Vec<_>>();
+        by_load.sort_by_key(|elf| std::cmp::Reverse(loads[*elf]));
+
+        let (heaviest, lightest) = (by_load[0], by_load[by_load.len() - 1]);
+
+        let transfer = by_load
+            .iter()
+            .map(|donor| (*donor, lightest))
+            .chain(by_load.iter().rev().map(|receiver| (heaviest, *receiver)))
+            .filter(|(from, to)| loads[*from] > loads[*to])
+            .find_map(|(from, to)| {
+                best_transfer(&backpacks[from], &backpacks[to], loads[from] - loads[to])
+                    .map(|(_, give, take)| (from, to, give, take))
+            });
+
+        let Some((from, to, give, take)) = transfer else {
+            break;
+        };
+
+        let snack = backpacks[from].swap_remove(give);
+        loads[from] -= snack;
+        loads[to] += snack;
+        moves.push(SnackMove { snack, from, to });
+
+        if let Some(take) = take {
+            let snack = backpacks[to].swap_remove(take);
+            loads[to] -= snack;
+            loads[from] += snack;
+            backpacks[from].push(snack);
+            moves.push(SnackMove {
+                snack,
+                from: to,
+                to: from,
+            });
+        }
+
+        backpacks[to].push(snack);
+    }
+
+    (backpacks, moves)
+}
+
+fn plan_rebalance(input: &str) {
+    let backpacks = backpacks(input);
+    let before = backpacks.iter().map(checked_sum).collect::<Vec<_>>();
+
+    let (balanced, moves) = rebalance(&backpacks);
+    let after = balanced.iter().map(checked_sum).collect::<Vec<_>>();
+
+    println!("Day 01 rebalance");
+    println!("\tMoves: {}", moves.len());
+    for SnackMove { snack, from, to } in &moves {
+        println!(
+            "\t\tmove {snack} calories from elf {} to elf {}",
+            from + 1,
+            to + 1
+        );
+    }
+
+    println!("\tLoads:");
+    for (elf, (before, after)) in before.iter().zip(&after).enumerate() {
+        println!("\t\tElf {:>4}: {:>6} -> {:>6}", elf + 1, before, after);
+    }
+
+    println!("\tSpread: {} -> {}", spread(&before), spread(&after));
 }
 
 pub(crate) fn run() {
@@ -38,3 +235,23 @@ pub(crate) fn run() {
     println!("\tPart 1: {}", part_1(input));
     println!("\tPart 2: {}", part_2(input));
 }
+
+pub(crate) fn run_with(args: &Args) {
+    let input = args.input(include_str!("../input/01.txt"));
+
+    match args.mode() {
+        Some("report") => {
+            let bucket_size = args.value("bucket-size").unwrap_or(HISTOGRAM_BUCKET_SIZE);
+            assert!(bucket_size > 0, "--bucket-size must be greater than 0");
+
+            report(&input, bucket_size)
+        }
+        Some("rebalance") => plan_rebalance(&input),
+        Some(mode) => panic!("unknown day 01 mode: {mode}"),
+        None => {
+            println!("Day 01");
+            println!("\tPart 1: {}", part_1(&input));
+            println!("\tPart 2: {}", part_2(&input));
+        }
+    }
+}
@@ -1,54 +1,218 @@
-enum Point {
-    One = 1,
-    Two = 2,
-    Three = 3,
-}
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::cli::Args;
 
+static ROCK_PAPER_SCISSORS: &str = "\
+# name     them me points beats
+Rock       A    X  1      Scissors
+Paper      B    Y  2      Rock
+Scissors   C    Z  3      Paper";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Point(i32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Outcome {
     Lose = 0,
     Draw = 3,
     Win = 6,
 }
 
-enum Shape {
-    Rock(Rock),
-    Paper(Paper),
-    Scissors(Scissors),
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Shape(usize);
+
+#[derive(Debug)]
+struct Rule {
+    name: String,
+    shape: Shape,
+    codes: (String, String),
+    points: Point,
+    beats: Vec<Shape>,
 }
 
-impl From<&str> for Shape {
-    fn from(s: &str) -> Shape {
-        match s {
-            "A" | "X" => Shape::Rock(Rock {}),
-            "B" | "Y" => Shape::Paper(Paper {}),
-            "C" | "Z" => Shape::Scissors(Scissors {}),
-            _ => unreachable!(),
+trait Play {
+    fn points(&self) -> Point;
+    fn outcome(&self, against: &Shape) -> Outcome;
+
+    fn play(&self, against: &Shape) -> i32 {
+        self.outcome(against) as i32 + self.points().0
+    }
+}
+
+impl Play for Rule {
+    fn points(&self) -> Point {
+        self.points
+    }
+
+    fn outcome(&self, against: &Shape) -> Outcome {
+        match against {
+            against if *against == self.shape => Outcome::Draw,
+            against if self.beats.contains(against) => Outcome::Win,
+            _ => Outcome::Lose,
         }
     }
 }
 
+#[derive(Debug)]
+struct Game(Vec<Rule>);
+
+impl Game {
+    fn from_table(table: &str) -> Self {
+        let rows = table
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let shape_named = |name: &str| {
+            rows.iter()
+                .position(|row| row[0] == name)
+                .map(Shape)
+                .unwrap_or_else(|| panic!("unknown shape in rule table: {name}"))
+        };
+
+        let rules = rows
+            .iter()
+            .enumerate()
+            .map(|(idx, row)| match row.as_slice() {
+                [name, them, me, points, beats @ ..] => Rule {
+                    name: name.to_string(),
+                    shape: Shape(idx),
+                    codes: (them.to_string(), me.to_string()),
+                    points: Point(points.parse().expect("rule points parse")),
+                    beats: beats
+                        .iter()
+                        .flat_map(|beats| beats.split(','))
+                        .map(shape_named)
+                        .unique()
+                        .collect(),
+                },
+                _ => panic!("malformed rule: {}", row.join(" ")),
+            })
+            .collect::<Vec<_>>();
+
+        Self(rules).validated()
+    }
+
+    // Odd-sized cyclic game where each shape beats the (n - 1) / 2 shapes listed before it,
+    // wrapping around. The order is the cycle, so Rock-Paper-Scissors-Spock-Lizard must be
+    // given as Rock,Spock,Paper,Lizard,Scissors; the usual naming order would have
+    // Scissors beat Rock.
+    fn cyclic(names: &[&str]) -> Self {
+        let n = names.len();
+
+        assert!(n <= 26, "at most 26 shapes can be given letter codes");
+
+        let table = names
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| {
+                let beats = (1..=n / 2)
+                    .map(|offset| names[(idx + n - offset) % n])
+                    .collect::<Vec<_>>()
+                    .join(",");
+
+                // Past Z the response column reuses the opponent's letter.
+                let them = char::from(b'A' + idx as u8);
+                let me = match idx {
+                    0..=2 => char::from(b'X' + idx as u8),
+                    _ => them,
+                };
+
+                format!("{name} {them} {me} {} {beats}", idx + 1)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Self::from_table(&table)
+    }
+
+    fn validated(self) -> Self {
+        let Game(rules) = &self;
+
+        assert!(
+            rules.len() % 2 == 1 && rules.len() >= 3,
+            "cyclic games need an odd number of shapes, at least 3"
+        );
+
+        // Each shape beating half of the others is what makes the game cyclic, and is what
+        // guarantees a win, a draw and a loss against every shape.
+        let half = (rules.len() - 1) / 2;
+        for rule in rules {
+            assert!(
+                rule.beats.len() == half,
+                "{} beats {} of the other shapes where a cyclic game needs {half}",
+                rule.name,
+                rule.beats.len()
+            );
+        }
+
+        for (a, b) in rules.iter().flat_map(|a| rules.iter().map(move |b| (a, b))) {
+            if a.shape == b.shape {
+                assert!(!a.beats.contains(&a.shape), "{} beats itself", a.name);
+                continue;
+            }
+
+            let (a_wins, b_wins) = (a.beats.contains(&b.shape), b.beats.contains(&a.shape));
+            assert!(
+                a_wins != b_wins,
+                "exactly one of {} and {} must beat the other",
+                a.name,
+                b.name
+            );
+        }
+
+        self
+    }
+
+    fn rule(&self, shape: &Shape) -> &Rule {
+        &self.0[shape.0]
+    }
+
+    // The opponent and response columns are separate code spaces, so the same letter may
+    // name a different shape in each.
+    fn opponent(&self, code: &str) -> Shape {
+        self.shape_in(code, |(them, _)| them)
+    }
+
+    fn response(&self, code: &str) -> Shape {
+        self.shape_in(code, |(_, me)| me)
+    }
+
+    fn shape_in(&self, code: &str, column: fn(&(String, String)) -> &String) -> Shape {
+        self.0
+            .iter()
+            .find(|rule| column(&rule.codes) == code)
+            .map(|rule| rule.shape)
+            .unwrap_or_else(|| panic!("unknown shape code: {code}"))
+    }
+
+    fn shape_for(&self, end: &EndRound, against: &Shape) -> Shape {
+        let wanted = match end {
+            EndRound::Win => Outcome::Win,
+            EndRound::Lose => Outcome::Lose,
+            EndRound::Draw => Outcome::Draw,
+        };
+
+        self.0
+            .iter()
+            .find(|rule| rule.outcome(against) == wanted)
+            .map(|rule| rule.shape)
+            .unwrap()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum EndRound {
     Win,
     Lose,
     Draw,
 }
 
-impl EndRound {
-    fn into_shape(&self, against: &Shape) -> Shape {
-        match (self, against) {
-            (EndRound::Win, Shape::Rock(_)) => Shape::Paper(Paper {}),
-            (EndRound::Win, Shape::Paper(_)) => Shape::Scissors(Scissors {}),
-            (EndRound::Win, Shape::Scissors(_)) => Shape::Rock(Rock {}),
-            (EndRound::Lose, Shape::Rock(_)) => Shape::Scissors(Scissors {}),
-            (EndRound::Lose, Shape::Paper(_)) => Shape::Rock(Rock {}),
-            (EndRound::Lose, Shape::Scissors(_)) => Shape::Paper(Paper {}),
-            (EndRound::Draw, Shape::Rock(_)) => Shape::Rock(Rock {}),
-            (EndRound::Draw, Shape::Paper(_)) => Shape::Paper(Paper {}),
-            (EndRound::Draw, Shape::Scissors(_)) => Shape::Scissors(Scissors {}),
-        }
-    }
-}
-
 impl From<&str> for EndRound {
     fn from(s: &str) -> Self {
         match s {
@@ -60,94 +224,548 @@ impl From<&str> for EndRound {
     }
 }
 
-trait Play {
-    const POINTS: Point;
+fn sum_rounds(input: &str, game: &Game, shape_parser: impl Fn(&Game, &str) -> Vec<Shape>) -> i32 {
+    input
+        .split('\n')
+        .map(|line| shape_parser(game, line))
+        .map(|round| game.rule(&round[1]).play(&round[0]))
+        .sum::<i32>()
+}
 
-    fn outcome(&self, against: &Shape) -> Outcome;
-    fn play(&self, against: &Shape) -> i32;
+fn shapes(game: &Game, line: &str) -> Vec<Shape> {
+    let codes = line.split_whitespace().collect::<Vec<_>>();
+    vec![game.opponent(codes[0]), game.response(codes[1])]
 }
 
-struct Rock;
+fn end_rounds(game: &Game, line: &str) -> Vec<Shape> {
+    let lines = line.split_whitespace().collect::<Vec<_>>();
+    let against = game.opponent(lines[0]);
+    let me = game.shape_for(&EndRound::from(lines[1]), &against);
+    vec![against, me]
+}
+
+fn part_1(input: &str) -> i32 {
+    sum_rounds(input, &Game::from_table(ROCK_PAPER_SCISSORS), shapes)
+}
 
-impl Play for Rock {
-    const POINTS: Point = Point::One;
+fn part_2(input: &str) -> i32 {
+    sum_rounds(input, &Game::from_table(ROCK_PAPER_SCISSORS), end_rounds)
+}
 
-    fn outcome(&self, against: &Shape) -> Outcome {
-        match against {
-            Shape::Rock(_) => Outcome::Draw,
-            Shape::Paper(_) => Outcome::Lose,
-            Shape::Scissors(_) => Outcome::Win,
+enum Reading {
+    Shapes(Vec<Shape>),
+    EndRounds(Vec<EndRound>),
+}
+
+impl Reading {
+    fn describe(&self, game: &Game) -> (&'static str, String) {
+        match self {
+            Reading::Shapes(shapes) => (
+                "shape",
+                shapes
+                    .iter()
+                    .map(|shape| game.rule(shape).name.clone())
+                    .join(" "),
+            ),
+            Reading::EndRounds(ends) => {
+                ("end", ends.iter().map(|end| format!("{end:?}")).join(" "))
+            }
         }
     }
+}
 
-    fn play(&self, against: &Shape) -> i32 {
-        self.outcome(against) as i32 + Self::POINTS as i32
+struct Decoding {
+    reading: Reading,
+    score: i32,
+    puzzle: bool,
+}
+
+fn response_codes(input: &str) -> Vec<&str> {
+    input
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .unique()
+        .sorted()
+        .collect()
+}
+
+fn decodings(input: &str, game: &Game) -> Vec<Decoding> {
+    let codes = response_codes(input);
+    let code_idx = |code: &str| codes.iter().position(|c| *c == code).unwrap();
+
+    let by_shape = game
+        .0
+        .iter()
+        .map(|rule| rule.shape)
+        .permutations(codes.len())
+        .map(|mapping| {
+            let puzzle = codes
+                .iter()
+                .zip(&mapping)
+                .all(|(code, shape)| game.response(code) == *shape);
+            let score = sum_rounds(input, game, |game, line| {
+                let round = line.split_whitespace().collect::<Vec<_>>();
+                vec![game.opponent(round[0]), mapping[code_idx(round[1])]]
+            });
+
+            Decoding {
+                reading: Reading::Shapes(mapping),
+                score,
+                puzzle,
+            }
+        });
+
+    let end_rounds = [EndRound::Lose, EndRound::Draw, EndRound::Win];
+    let by_end_round = end_rounds
+        .into_iter()
+        .permutations(end_rounds.len())
+        .filter(|_| codes.len() == end_rounds.len())
+        .map(|mapping| {
+            let puzzle = codes
+                .iter()
+                .zip(&mapping)
+                .all(|(code, end)| EndRound::from(*code) == *end);
+            let score = sum_rounds(input, game, |game, line| {
+                let round = line.split_whitespace().collect::<Vec<_>>();
+                let against = game.opponent(round[0]);
+                vec![
+                    against,
+                    game.shape_for(&mapping[code_idx(round[1])], &against),
+                ]
+            });
+
+            Decoding {
+                reading: Reading::EndRounds(mapping),
+                score,
+                puzzle,
+            }
+        });
+
+    by_shape.chain(by_end_round).collect()
+}
+
+fn print_sensitivity(kind: &str, scores: &[i32]) {
+    if scores.is_empty() {
+        return;
     }
+
+    let mean = scores.iter().sum::<i32>() as f64 / scores.len() as f64;
+    let variance = scores
+        .iter()
+        .map(|score| (*score as f64 - mean).powi(2))
+        .sum::<f64>()
+        / scores.len() as f64;
+    let (min, max) = (scores.iter().min().unwrap(), scores.iter().max().unwrap());
+
+    println!(
+        "\t{kind:<6} min {min:>6}  max {max:>6}  range {:>6}  mean {mean:>9.1}  stddev {:>8.1}",
+        max - min,
+        variance.sqrt()
+    );
 }
 
-struct Paper;
+fn analyse_decodings(input: &str, game: &Game, sort: Option<&str>) {
+    let codes = response_codes(input);
+    let mut decodings = decodings(input, game);
 
-impl Play for Paper {
-    const POINTS: Point = Point::Two;
+    match sort {
+        Some("score") => decodings.sort_by_key(|decoding| std::cmp::Reverse(decoding.score)),
+        Some("kind") | None => (),
+        Some(key) => panic!("unknown sort key: {key}"),
+    }
 
-    fn outcome(&self, against: &Shape) -> Outcome {
-        match against {
-            Shape::Rock(_) => Outcome::Win,
-            Shape::Paper(_) => Outcome::Draw,
-            Shape::Scissors(_) => Outcome::Lose,
+    println!("Day 02 decodings");
+    println!("\t{:<6} {:<30} {:>6}", "kind", codes.join(" "), "score");
+
+    for Decoding {
+        reading,
+        score,
+        puzzle,
+    } in &decodings
+    {
+        let (kind, mapping) = reading.describe(game);
+
+        let marker = if *puzzle { " *" } else { "" };
+        println!("\t{kind:<6} {mapping:<30} {score:>6}{marker}");
+    }
+
+    let best = decodings
+        .iter()
+        .max_by_key(|decoding| decoding.score)
+        .unwrap();
+    let worst = decodings
+        .iter()
+        .min_by_key(|decoding| decoding.score)
+        .unwrap();
+    for (label, decoding) in [("Best", best), ("Worst", worst)] {
+        let (kind, mapping) = decoding.reading.describe(game);
+        println!("\t{label}: {} ({kind} {mapping})", decoding.score);
+    }
+
+    println!("\tSensitivity:");
+    let scores_of = |shapes: bool| {
+        decodings
+            .iter()
+            .filter(|decoding| matches!(decoding.reading, Reading::Shapes(_)) == shapes)
+            .map(|decoding| decoding.score)
+            .collect::<Vec<_>>()
+    };
+    print_sensitivity("shape", &scores_of(true));
+    print_sensitivity("end", &scores_of(false));
+}
+
+trait Strategy {
+    fn choose(&mut self, game: &Game, rng: &mut StdRng) -> Shape;
+    fn observe(&mut self, opponent: Shape);
+}
+
+fn random_shape(game: &Game, rng: &mut StdRng) -> Shape {
+    game.0[rng.gen_range(0..game.0.len())].shape
+}
+
+fn beat(game: &Game, predicted: Option<Shape>, rng: &mut StdRng) -> Shape {
+    match predicted {
+        Some(predicted) => game.shape_for(&EndRound::Win, &predicted),
+        None => random_shape(game, rng),
+    }
+}
+
+// Most common key, with ties broken at random.
+fn most_common(counts: &HashMap<Shape, u32>, rng: &mut StdRng) -> Option<Shape> {
+    let top = counts.values().max()?;
+    let tied = counts
+        .iter()
+        .filter(|(_, count)| *count == top)
+        .map(|(shape, _)| *shape)
+        .sorted_by_key(|shape| shape.0)
+        .collect::<Vec<_>>();
+
+    tied.choose(rng).copied()
+}
+
+struct RandomBot;
+
+impl Strategy for RandomBot {
+    fn choose(&mut self, game: &Game, rng: &mut StdRng) -> Shape {
+        random_shape(game, rng)
+    }
+
+    fn observe(&mut self, _opponent: Shape) {}
+}
+
+#[derive(Default)]
+struct CopyLastBot(Option<Shape>);
+
+impl Strategy for CopyLastBot {
+    fn choose(&mut self, game: &Game, rng: &mut StdRng) -> Shape {
+        self.0.unwrap_or_else(|| random_shape(game, rng))
+    }
+
+    fn observe(&mut self, opponent: Shape) {
+        self.0 = Some(opponent);
+    }
+}
+
+#[derive(Default)]
+struct FrequencyBot(HashMap<Shape, u32>);
+
+impl Strategy for FrequencyBot {
+    fn choose(&mut self, game: &Game, rng: &mut StdRng) -> Shape {
+        let predicted = most_common(&self.0, rng);
+        beat(game, predicted, rng)
+    }
+
+    fn observe(&mut self, opponent: Shape) {
+        *self.0.entry(opponent).or_default() += 1;
+    }
+}
+
+#[derive(Default)]
+struct MarkovBot {
+    last: Option<Shape>,
+    transitions: HashMap<Shape, HashMap<Shape, u32>>,
+}
+
+impl Strategy for MarkovBot {
+    fn choose(&mut self, game: &Game, rng: &mut StdRng) -> Shape {
+        let predicted = self
+            .last
+            .and_then(|last| self.transitions.get(&last))
+            .and_then(|next| most_common(next, rng));
+
+        beat(game, predicted, rng)
+    }
+
+    fn observe(&mut self, opponent: Shape) {
+        if let Some(last) = self.last {
+            *self
+                .transitions
+                .entry(last)
+                .or_default()
+                .entry(opponent)
+                .or_default() += 1;
         }
+
+        self.last = Some(opponent);
     }
+}
 
-    fn play(&self, against: &Shape) -> i32 {
-        self.outcome(against) as i32 + Self::POINTS as i32
+static BOTS: [&str; 4] = ["random", "copy-last", "frequency", "markov"];
+
+fn make_bot(name: &str) -> Box<dyn Strategy> {
+    match name {
+        "random" => Box::new(RandomBot),
+        "copy-last" => Box::new(CopyLastBot::default()),
+        "frequency" => Box::new(FrequencyBot::default()),
+        "markov" => Box::new(MarkovBot::default()),
+        _ => panic!("unknown bot: {name}"),
     }
 }
 
-struct Scissors;
+#[derive(Default)]
+struct Tally {
+    wins: u64,
+    draws: u64,
+    losses: u64,
+    scores: Vec<i32>,
+}
 
-impl Play for Scissors {
-    const POINTS: Point = Point::Three;
+// Each game replays a seeded window of the guide's second column against a fresh bot,
+// which takes the place of the guide's first column.
+fn simulate(
+    input: &str,
+    game: &Game,
+    bot_name: &str,
+    reading: &str,
+    games: usize,
+    rounds: usize,
+    rng: &mut StdRng,
+) -> Tally {
+    let codes = input
+        .lines()
+        .map(|line| line.split_whitespace().nth(1).unwrap())
+        .collect::<Vec<_>>();
+    let rounds = rounds.min(codes.len());
+    let mut tally = Tally::default();
 
-    fn outcome(&self, against: &Shape) -> Outcome {
-        match against {
-            Shape::Rock(_) => Outcome::Lose,
-            Shape::Paper(_) => Outcome::Win,
-            Shape::Scissors(_) => Outcome::Draw,
+    for _ in 0..games {
+        let mut bot = make_bot(bot_name);
+        let start = rng.gen_range(0..=codes.len() - rounds);
+        let mut score = 0;
+
+        for code in &codes[start..start + rounds] {
+            let against = bot.choose(game, rng);
+            let me = match reading {
+                "shape" => game.response(code),
+                "end" => game.shape_for(&EndRound::from(*code), &against),
+                _ => panic!("unknown reading: {reading}"),
+            };
+
+            let rule = game.rule(&me);
+            match rule.outcome(&against) {
+                Outcome::Win => tally.wins += 1,
+                Outcome::Draw => tally.draws += 1,
+                Outcome::Lose => tally.losses += 1,
+            }
+            score += rule.play(&against);
+
+            bot.observe(me);
         }
+
+        tally.scores.push(score);
     }
 
-    fn play(&self, against: &Shape) -> i32 {
-        self.outcome(against) as i32 + Self::POINTS as i32
+    tally
+}
+
+fn tournament(input: &str, game: &Game, args: &Args) {
+    let seed = args.value("seed").unwrap_or(2022);
+    let games = args.value("games").unwrap_or(1000);
+    let rounds = args.value("rounds").unwrap_or(usize::MAX);
+
+    assert!(games >= 1, "--games must be at least 1");
+    assert!(rounds >= 1, "--rounds must be at least 1");
+    let reading = args
+        .value::<String>("reading")
+        .unwrap_or("shape".to_owned());
+    let bots = match args.value::<String>("bot") {
+        Some(bot) => vec![bot],
+        None => BOTS.map(String::from).to_vec(),
+    };
+
+    println!("Day 02 tournament ({games} games, seed {seed}, {reading} reading)");
+    println!(
+        "\t{:<10} {:>6} {:>6} {:>6} {:>8} {:>8} {:>8} {:>8} {:>8}",
+        "bot", "win%", "draw%", "loss%", "min", "p10", "median", "p90", "max"
+    );
+
+    for bot in &bots {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut tally = simulate(input, game, bot, &reading, games, rounds, &mut rng);
+
+        let played = (tally.wins + tally.draws + tally.losses) as f64;
+        let rate = |count: u64| 100. * count as f64 / played;
+        tally.scores.sort();
+        let pct = |p: usize| tally.scores[(p * (tally.scores.len() - 1)) / 100];
+
+        println!(
+            "\t{bot:<10} {:>6.2} {:>6.2} {:>6.2} {:>8} {:>8} {:>8} {:>8} {:>8}",
+            rate(tally.wins),
+            rate(tally.draws),
+            rate(tally.losses),
+            pct(0),
+            pct(10),
+            pct(50),
+            pct(90),
+            pct(100)
+        );
     }
 }
 
-fn sum_rounds(input: &str, shape_parser: fn(&str) -> Vec<Shape>) -> i32 {
-    input
-        .split("\n")
-        .map(shape_parser)
-        .map(|round| match &round[1] {
-            Shape::Rock(r) => r.play(&round[0]),
-            Shape::Paper(p) => p.play(&round[0]),
-            Shape::Scissors(s) => s.play(&round[0]),
-        })
-        .sum::<i32>()
+// Every (code, shape) pair a guide line may answer `against` with under the given reading.
+fn responses(game: &Game, reading: &str, against: &Shape) -> Vec<(String, Shape)> {
+    match reading {
+        "shape" => game
+            .0
+            .iter()
+            .map(|rule| (rule.codes.1.clone(), rule.shape))
+            .collect(),
+        "end" => ["X", "Y", "Z"]
+            .into_iter()
+            .map(|code| {
+                (
+                    code.to_owned(),
+                    game.shape_for(&EndRound::from(code), against),
+                )
+            })
+            .collect(),
+        _ => panic!("unknown reading: {reading}"),
+    }
 }
 
-fn part_1(input: &str) -> i32 {
-    sum_rounds(input, |line: &str| {
-        line.split_whitespace()
-            .map(|shape| Shape::from(shape))
-            .collect::<Vec<_>>()
-    })
+// Knapsack-style DP over (round, score) minimising wins; `None` means the target is
+// unreachable with the given opponent column.
+fn guide_for_score(
+    game: &Game,
+    reading: &str,
+    opponent: &[Shape],
+    target: usize,
+) -> Option<(u32, Vec<String>)> {
+    const UNREACHABLE: u32 = u32::MAX;
+
+    let options = opponent
+        .iter()
+        .map(|against| {
+            responses(game, reading, against)
+                .into_iter()
+                .map(|(code, me)| {
+                    let rule = game.rule(&me);
+                    (
+                        code,
+                        rule.play(against) as usize,
+                        rule.outcome(against) == Outcome::Win,
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // The tables are sized by the target, so rule out scores beyond every guide first.
+    let (lowest, highest) = options.iter().fold((0, 0), |(low, high), round| {
+        let points = round.iter().map(|(_, points, _)| *points);
+        (
+            low + points.clone().min().unwrap(),
+            high + points.max().unwrap(),
+        )
+    });
+    if !(lowest..=highest).contains(&target) {
+        return None;
+    }
+
+    let mut fewest_wins = vec![UNREACHABLE; target + 1];
+    fewest_wins[0] = 0;
+    let mut choices = Vec::with_capacity(options.len());
+
+    for round in &options {
+        let mut next = vec![UNREACHABLE; target + 1];
+        let mut choice = vec![u8::MAX; target + 1];
+
+        for (score, wins) in fewest_wins
+            .iter()
+            .enumerate()
+            .filter(|(_, wins)| **wins != UNREACHABLE)
+        {
+            for (idx, (_, points, win)) in round.iter().enumerate() {
+                let (score, wins) = (score + points, wins + *win as u32);
+
+                if score <= target && wins < next[score] {
+                    next[score] = wins;
+                    choice[score] = idx as u8;
+                }
+            }
+        }
+
+        fewest_wins = next;
+        choices.push(choice);
+    }
+
+    if fewest_wins[target] == UNREACHABLE {
+        return None;
+    }
+
+    let mut score = target;
+    let mut guide = vec![];
+
+    for (round, choice) in options.iter().zip(&choices).rev() {
+        let (code, points, _) = &round[choice[score] as usize];
+        guide.push(code.clone());
+        score -= points;
+    }
+    guide.reverse();
+
+    Some((fewest_wins[target], guide))
 }
 
-fn part_2(input: &str) -> i32 {
-    sum_rounds(input, |line| {
-        let lines = line.split_whitespace().collect::<Vec<_>>();
-        let against = Shape::from(lines[0]);
-        let me = EndRound::from(lines[1]).into_shape(&against);
-        vec![against, me]
-    })
+fn reverse_engineer(input: &str, game: &Game, args: &Args) {
+    let target = args.value::<usize>("score").expect("--score is required");
+    let reading = args
+        .value::<String>("reading")
+        .unwrap_or("shape".to_owned());
+
+    let them = input
+        .lines()
+        .map(|line| line.split_whitespace().next().unwrap())
+        .collect::<Vec<_>>();
+    let opponent = them
+        .iter()
+        .map(|code| game.opponent(code))
+        .collect::<Vec<_>>();
+
+    println!("Day 02 target {target} ({reading} reading)");
+
+    let score_range = opponent.iter().fold((0, 0), |(low, high), against| {
+        let scores = responses(game, &reading, against)
+            .iter()
+            .map(|(_, me)| game.rule(me).play(against))
+            .collect::<Vec<_>>();
+        (
+            low + scores.iter().min().unwrap(),
+            high + scores.iter().max().unwrap(),
+        )
+    });
+
+    match guide_for_score(game, &reading, &opponent, target) {
+        Some((wins, guide)) => {
+            println!("\tReachable with {wins} wins");
+            for (them, me) in them.iter().zip(&guide) {
+                println!("{them} {me}");
+            }
+        }
+        None => println!(
+            "\tUnreachable: achievable scores lie in {}..={} and no combination of responses sums to {target}",
+            score_range.0, score_range.1
+        ),
+    }
 }
 
 pub(crate) fn run() {
@@ -157,3 +775,49 @@ pub(crate) fn run() {
     println!("\tPart 1: {}", part_1(input));
     println!("\tPart 2: {}", part_2(input));
 }
+
+pub(crate) fn run_with(args: &Args) {
+    let input = args.input(include_str!("../input/02.txt"));
+
+    let game = match (
+        args.value::<String>("rules"),
+        args.value::<String>("cyclic"),
+    ) {
+        (Some(path), _) => Game::from_table(
+            &std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{path}: {e}")),
+        ),
+        (None, Some(names)) => Game::cyclic(&names.split(',').collect::<Vec<_>>()),
+        (None, None) => Game::from_table(ROCK_PAPER_SCISSORS),
+    };
+
+    match args.mode() {
+        Some("rules") => {
+            println!("Day 02 rules");
+            for rule in &game.0 {
+                let beats = rule
+                    .beats
+                    .iter()
+                    .map(|shape| game.rule(shape).name.as_str());
+                println!(
+                    "\t{:<10} {}/{} {:>2} points, beats {}",
+                    rule.name,
+                    rule.codes.0,
+                    rule.codes.1,
+                    rule.points.0,
+                    beats.collect::<Vec<_>>().join(", ")
+                );
+            }
+        }
+        Some("decodings") => {
+            analyse_decodings(&input, &game, args.value::<String>("sort").as_deref())
+        }
+        Some("tournament") => tournament(&input, &game, args),
+        Some("target") => reverse_engineer(&input, &game, args),
+        Some(mode) => panic!("unknown day 02 mode: {mode}"),
+        None => {
+            println!("Day 02");
+            println!("\tPart 1: {}", sum_rounds(&input, &game, shapes));
+            println!("\tPart 2: {}", sum_rounds(&input, &game, end_rounds));
+        }
+    }
+}
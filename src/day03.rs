@@ -1,6 +1,10 @@
-use itertools::iproduct;
+use std::{collections::BTreeMap, fmt::Display};
 
-#[derive(Clone, PartialEq, Eq)]
+use itertools::Itertools;
+
+use crate::cli::Args;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 struct Item(u8);
 
 impl Item {
@@ -8,6 +12,18 @@ impl Item {
         Self(c as u8)
     }
 
+    fn checked(c: char) -> Option<Self> {
+        c.is_ascii_alphabetic().then(|| Self::new(c))
+    }
+
+    fn from_priority(priority: u32) -> Self {
+        match priority {
+            1..=26 => Self(b'a' + (priority - 1) as u8),
+            27..=52 => Self(b'A' + (priority - 27) as u8),
+            _ => unreachable!(),
+        }
+    }
+
     fn priority(&self) -> i32 {
         match self.0 {
             65..=90 => 27 + (self.0 - 65) as i32,
@@ -17,6 +33,61 @@ impl Item {
     }
 }
 
+impl Display for Item {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", char::from(self.0))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ItemSet(u64);
+
+impl ItemSet {
+    fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    fn contains(self, item: &Item) -> bool {
+        self.intersection(ItemSet::from(item)) != ItemSet::default()
+    }
+
+    fn first(self) -> Option<Item> {
+        self.iter().next()
+    }
+
+    fn iter(self) -> impl Iterator<Item = Item> {
+        let mut mask = self.0;
+
+        std::iter::from_fn(move || match mask {
+            0 => None,
+            _ => {
+                let priority = mask.trailing_zeros();
+                mask &= mask - 1;
+                Some(Item::from_priority(priority))
+            }
+        })
+    }
+}
+
+impl From<&Item> for ItemSet {
+    fn from(item: &Item) -> Self {
+        Self(1 << item.priority())
+    }
+}
+
+impl<'a> FromIterator<&'a Item> for ItemSet {
+    fn from_iter<T: IntoIterator<Item = &'a Item>>(items: T) -> Self {
+        items
+            .into_iter()
+            .map(ItemSet::from)
+            .fold(ItemSet::default(), ItemSet::union)
+    }
+}
+
 struct Rucksack(Vec<Item>);
 
 impl Rucksack {
@@ -28,50 +99,351 @@ impl Rucksack {
         &self.0
     }
 
-    fn equal_item(&self) -> Option<&Item> {
-        let (left, right) = self.items().split_at(self.items().len() / 2);
+    fn item_set(&self) -> ItemSet {
+        self.items().iter().collect()
+    }
+
+    fn compartments(&self, count: usize) -> Option<std::slice::Chunks<'_, Item>> {
+        match self.items().len() {
+            0 => None,
+            len if len % count == 0 => Some(self.items().chunks(len / count)),
+            _ => None,
+        }
+    }
+
+    fn shared_items(&self, compartments: usize) -> Option<ItemSet> {
+        self.compartments(compartments).map(|compartments| {
+            compartments
+                .map(|compartment| compartment.iter().collect::<ItemSet>())
+                .reduce(ItemSet::intersection)
+                .unwrap_or_default()
+        })
+    }
+
+    fn equal_item(&self, compartments: usize) -> Option<Option<Item>> {
+        self.shared_items(compartments).map(ItemSet::first)
+    }
+
+    // Each item type ends up wholly in one compartment. Choosing the set of types kept
+    // on the left is a knapsack: their total count must be exactly half the rucksack,
+    // and every right-hand item of a left type costs one swap.
+    fn repack_plan(&self) -> Option<Vec<Swap>> {
+        let half = self.items().len() / 2;
+        let (left, right) = self.items().split_at(half);
+
+        let count = |side: &[Item], item: &Item| side.iter().filter(|i| *i == item).count();
+        let types = self
+            .item_set()
+            .iter()
+            .map(|item| (item, count(left, &item), count(right, &item)))
+            .collect::<Vec<_>>();
 
-        iproduct!(left, right)
-            .find(|(l, r)| l == r)
-            .map(|(l, _r)| l)
+        // fewest[t][c]: fewest swaps filling c left slots with the first t types.
+        let mut fewest = vec![vec![None; half + 1]; types.len() + 1];
+        fewest[0][0] = Some(0);
+
+        for (t, (_, in_left, in_right)) in types.iter().enumerate() {
+            for c in 0..=half {
+                let keep_right = fewest[t][c];
+                let keep_left = c
+                    .checked_sub(in_left + in_right)
+                    .and_then(|rest| fewest[t][rest])
+                    .map(|swaps| swaps + in_right);
+
+                fewest[t + 1][c] = match (keep_left, keep_right) {
+                    (Some(l), Some(r)) => Some(l.min(r)),
+                    (l, r) => l.or(r),
+                };
+            }
+        }
+
+        fewest[types.len()][half]?;
+
+        let mut left_types = ItemSet::default();
+        let mut c = half;
+        for (t, (item, in_left, in_right)) in types.iter().enumerate().rev() {
+            if fewest[t + 1][c] != fewest[t][c] {
+                left_types = left_types.union(ItemSet::from(item));
+                c -= in_left + in_right;
+            }
+        }
+
+        let to_right = left.iter().positions(|item| !left_types.contains(item));
+        let to_left = right
+            .iter()
+            .positions(|item| left_types.contains(item))
+            .map(|pos| pos + half);
+
+        Some(
+            to_right
+                .zip(to_left)
+                .map(|(left, right)| Swap { left, right })
+                .collect(),
+        )
+    }
+}
+
+struct Swap {
+    left: usize,
+    right: usize,
+}
+
+#[derive(Debug)]
+enum RucksackError {
+    UnevenCompartments {
+        line: usize,
+        len: usize,
+        compartments: usize,
+    },
+    IncompleteGroup {
+        line: usize,
+        len: usize,
+        group_size: usize,
+    },
+    NoBadge {
+        line: usize,
+    },
+    EmptyRucksack {
+        line: usize,
+    },
+}
+
+impl RucksackError {
+    fn unsplittable(line: usize, rucksack: &Rucksack, compartments: usize) -> Self {
+        match rucksack.items().len() {
+            0 => RucksackError::EmptyRucksack { line },
+            len => RucksackError::UnevenCompartments {
+                line,
+                len,
+                compartments,
+            },
+        }
+    }
+}
+
+impl Display for RucksackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RucksackError::UnevenCompartments {
+                line,
+                len,
+                compartments,
+            } => write!(
+                f,
+                "line {line}: {len} items cannot be split into {compartments} equal compartments"
+            ),
+            RucksackError::IncompleteGroup {
+                line,
+                len,
+                group_size,
+            } => write!(
+                f,
+                "line {line}: last group has {len} rucksacks, expected {group_size}"
+            ),
+            RucksackError::EmptyRucksack { line } => write!(f, "line {line}: empty rucksack"),
+            RucksackError::NoBadge { line } => {
+                write!(
+                    f,
+                    "group at line {line}: no item is common to every rucksack"
+                )
+            }
+        }
     }
 }
 
 fn make_rucksacks(input: &str) -> Vec<Rucksack> {
     input
         .lines()
-        .map(|rucksack| {
-            Rucksack::new(
-                &rucksack
-                    .chars()
-                    .map(|item| Item::new(item))
-                    .collect::<Vec<_>>(),
-            )
-        })
+        .map(|rucksack| Rucksack::new(&rucksack.chars().map(Item::new).collect::<Vec<_>>()))
         .collect()
 }
 
-fn part_1(input: &str) -> i32 {
+fn sum_misplaced(input: &str, compartments: usize) -> Result<i32, RucksackError> {
     make_rucksacks(input)
         .iter()
-        .map(|rucksack| rucksack.equal_item().map(|item| item.priority()).unwrap())
+        .enumerate()
+        .map(|(idx, rucksack)| match rucksack.equal_item(compartments) {
+            Some(item) => Ok(item.map_or(0, |item| item.priority())),
+            None => Err(RucksackError::unsplittable(idx + 1, rucksack, compartments)),
+        })
         .sum()
 }
 
-fn part_2(input: &str) -> i32 {
+fn sum_badges(input: &str, group_size: usize) -> Result<i32, RucksackError> {
     make_rucksacks(input)
-        .chunks_exact(3)
-        .map(|group| {
-            let (f, s, t) = (&group[0], &group[1], &group[2]);
-
-            iproduct!(f.items(), s.items(), t.items())
-                .find(|(f, s, t)| f == s && s == t)
-                .map(|(f, _, _)| f.priority())
-                .unwrap()
+        .chunks(group_size)
+        .enumerate()
+        .map(|(idx, group)| match group.len() {
+            len if len == group_size => group
+                .iter()
+                .map(Rucksack::item_set)
+                .reduce(ItemSet::intersection)
+                .and_then(ItemSet::first)
+                .map(|badge| badge.priority())
+                .ok_or(RucksackError::NoBadge {
+                    line: idx * group_size + 1,
+                }),
+            len => Err(RucksackError::IncompleteGroup {
+                line: idx * group_size + 1,
+                len,
+                group_size,
+            }),
         })
         .sum()
 }
 
+fn part_1(input: &str) -> i32 {
+    sum_misplaced(input, 2).unwrap()
+}
+
+fn part_2(input: &str) -> i32 {
+    sum_badges(input, 3).unwrap()
+}
+
+// Compartments are split by position in the raw line, so an invalid item leaves a gap
+// in its own compartment instead of shifting the boundary.
+fn shared_by_position(
+    slots: &[Option<Item>],
+    line: usize,
+    compartments: usize,
+) -> Result<ItemSet, RucksackError> {
+    match slots.len() {
+        0 => Err(RucksackError::EmptyRucksack { line }),
+        len if len % compartments != 0 => Err(RucksackError::UnevenCompartments {
+            line,
+            len,
+            compartments,
+        }),
+        len => Ok(slots
+            .chunks(len / compartments)
+            .map(|compartment| compartment.iter().flatten().collect::<ItemSet>())
+            .reduce(ItemSet::intersection)
+            .unwrap_or_default()),
+    }
+}
+
+fn audit(input: &str, compartments: usize, group_size: usize) {
+    let mut invalid = vec![];
+    let slots = input
+        .lines()
+        .enumerate()
+        .map(|(line, rucksack)| {
+            rucksack
+                .chars()
+                .enumerate()
+                .map(|(column, c)| {
+                    let item = Item::checked(c);
+                    if item.is_none() {
+                        invalid.push((line + 1, column + 1, c));
+                    }
+                    item
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let rucksacks = slots
+        .iter()
+        .map(|slots| Rucksack::new(&slots.iter().flatten().copied().collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+
+    let mut frequencies = BTreeMap::<i32, (Item, usize)>::new();
+
+    println!("Day 03 audit ({compartments} compartments, groups of {group_size})");
+
+    println!("\tMisplaced items:");
+    for (idx, slots) in slots.iter().enumerate() {
+        match shared_by_position(slots, idx + 1, compartments) {
+            Ok(shared) => {
+                for item in shared.iter() {
+                    frequencies.entry(item.priority()).or_insert((item, 0)).1 += 1;
+                }
+                println!("\t\tline {:>4}: {}", idx + 1, shared.iter().join(", "));
+            }
+            Err(e) => println!("\t\t{e}"),
+        }
+    }
+
+    println!("\tInvalid items:");
+    for (line, column, c) in &invalid {
+        println!("\t\tline {line:>4}, column {column:>3}: {c:?}");
+    }
+
+    println!("\tBadge anomalies:");
+    for (idx, group) in rucksacks.chunks(group_size).enumerate() {
+        let line = idx * group_size + 1;
+
+        if group.len() != group_size {
+            let e = RucksackError::IncompleteGroup {
+                line,
+                len: group.len(),
+                group_size,
+            };
+            println!("\t\t{e}");
+            continue;
+        }
+
+        let candidates = group
+            .iter()
+            .map(Rucksack::item_set)
+            .reduce(ItemSet::intersection)
+            .unwrap_or_default();
+
+        match candidates.iter().count() {
+            1 => (),
+            0 => println!("\t\tgroup at line {line:>4}: no badge candidate"),
+            n => println!(
+                "\t\tgroup at line {line:>4}: {n} badge candidates: {}",
+                candidates.iter().join(", ")
+            ),
+        }
+    }
+
+    println!("\tMisplacement frequencies:");
+    for (item, count) in frequencies
+        .values()
+        .sorted_by_key(|(item, count)| (std::cmp::Reverse(*count), item.priority()))
+    {
+        println!("\t\t{item}: {count}");
+    }
+}
+
+fn plan_repacking(input: &str) {
+    let rucksacks = make_rucksacks(input);
+    let (mut swaps, mut untouched, mut impossible) = (0, 0, 0);
+
+    println!("Day 03 repacking plan");
+
+    for (idx, rucksack) in rucksacks.iter().enumerate() {
+        let line = idx + 1;
+
+        match rucksack.repack_plan() {
+            Some(plan) if plan.is_empty() => untouched += 1,
+            Some(plan) => {
+                swaps += plan.len();
+                let items = rucksack.items();
+                let plan = plan.iter().map(|Swap { left, right }| {
+                    format!(
+                        "{}@{} <-> {}@{}",
+                        items[*left],
+                        left + 1,
+                        items[*right],
+                        right + 1
+                    )
+                });
+                println!("\tline {line:>4}: {}", plan.collect::<Vec<_>>().join(", "));
+            }
+            None => {
+                impossible += 1;
+                println!("\tline {line:>4}: no plan keeps both compartments the same size");
+            }
+        }
+    }
+
+    println!("\tTotal swaps: {swaps}");
+    println!("\tAlready separated: {untouched}");
+    println!("\tWithout a plan: {impossible}");
+}
+
 pub(crate) fn run() {
     let input = include_str!("../input/03.txt");
 
@@ -79,3 +451,28 @@ pub(crate) fn run() {
     println!("\tPart 1: {}", part_1(input));
     println!("\tPart 2: {}", part_2(input));
 }
+
+pub(crate) fn run_with(args: &Args) {
+    let input = args.input(include_str!("../input/03.txt"));
+    let compartments = args.value("compartments").unwrap_or(2);
+    let group_size = args.value("group-size").unwrap_or(3);
+
+    assert!(compartments >= 2, "--compartments must be at least 2");
+    assert!(group_size >= 2, "--group-size must be at least 2");
+
+    match args.mode() {
+        Some("repack") => plan_repacking(&input),
+        Some("audit") => audit(&input, compartments, group_size),
+        Some(mode) => panic!("unknown day 03 mode: {mode}"),
+        None => {
+            let report = |result: Result<i32, RucksackError>| match result {
+                Ok(sum) => sum.to_string(),
+                Err(e) => format!("error: {e}"),
+            };
+
+            println!("Day 03 ({compartments} compartments, groups of {group_size})");
+            println!("\tPart 1: {}", report(sum_misplaced(&input, compartments)));
+            println!("\tPart 2: {}", report(sum_badges(&input, group_size)));
+        }
+    }
+}
@@ -1,51 +1,47 @@
+use std::collections::HashMap;
+
 use itertools::Itertools;
 
-struct SectionRange(u8, u8);
+use crate::{
+    cli::Args,
+    interval::{Interval, IntervalSet},
+};
+
+struct SectionRange(Interval<u64>);
 
 impl SectionRange {
     fn from_raw(range: &str) -> Self {
-        let section = range.split("-").collect::<Vec<_>>();
-        let begin = str::parse::<u8>(section[0]).expect("begin section parse");
-        let end = str::parse::<u8>(section[1]).expect("end section parse");
+        let section = range.split('-').collect::<Vec<_>>();
+        let begin = str::parse::<u64>(section[0]).expect("begin section parse");
+        let end = str::parse::<u64>(section[1]).expect("end section parse");
 
-        Self(begin, end)
+        Self(Interval::new(begin, end))
     }
 }
 
-struct Section(SectionRange, SectionRange);
+struct Section(Vec<SectionRange>);
 
 impl Section {
     fn from_raw(section: &str) -> Self {
-        let ranges = section.split(",").collect::<Vec<_>>();
-        let left = SectionRange::from_raw(ranges[0]);
-        let right = SectionRange::from_raw(ranges[1]);
-
-        Self(left, right)
+        Self(section.split(',').map(SectionRange::from_raw).collect())
     }
 
     fn fully_overlaps(&self) -> bool {
-        let SectionRange(left_begin, left_end) = self.0;
-        let SectionRange(right_begin, right_end) = self.1;
-
-        match (left_begin, right_begin) {
-            _ if left_begin == right_begin => true,
-            _ if left_begin > right_begin => right_end >= left_end,
-            _ if left_begin < right_begin => right_end <= left_end,
-            _ => unreachable!(),
-        }
+        self.0
+            .iter()
+            .tuple_combinations()
+            .any(|(SectionRange(left), SectionRange(right))| {
+                left.contains_interval(right) || right.contains_interval(left)
+            })
     }
 
     fn any_overlaps(&self) -> bool {
-        let SectionRange(left_begin, left_end) = self.0;
-        let SectionRange(right_begin, right_end) = self.1;
-
-        ![
-            (left_begin..=left_end).collect::<Vec<_>>(),
-            (right_begin..=right_end).collect::<Vec<_>>(),
-        ]
-        .concat()
-        .iter()
-        .all_unique()
+        self.0
+            .iter()
+            .map(|SectionRange(range)| range)
+            .sorted()
+            .tuple_windows()
+            .any(|(left, right)| left.overlaps(right))
     }
 }
 
@@ -65,6 +61,331 @@ fn part_2(input: &str) -> i32 {
     count_overlaps(input, |section| section.any_overlaps())
 }
 
+// Maximal runs of sections assigned to the same number of elves, from a sweep over
+// every range's start and end. Events are widened so a range ending at `u64::MAX` can
+// still close one past its end.
+fn depth_runs(ranges: &[&Interval<u64>]) -> Vec<(Interval<u64>, usize)> {
+    let events = ranges
+        .iter()
+        .flat_map(|range| [(range.start() as u128, 1), (range.end() as u128 + 1, -1)])
+        .sorted()
+        .collect::<Vec<_>>();
+
+    let mut runs = vec![];
+    let mut depth = 0i64;
+
+    for (idx, (position, delta)) in events.iter().enumerate() {
+        depth += delta;
+
+        match events.get(idx + 1) {
+            Some((next, _)) if next > position => runs.push((
+                Interval::new(*position as u64, (next - 1) as u64),
+                depth as usize,
+            )),
+            _ => (),
+        }
+    }
+
+    runs
+}
+
+fn coverage(input: &str) {
+    let sections = input.lines().map(Section::from_raw).collect::<Vec<_>>();
+    let ranges = sections
+        .iter()
+        .flat_map(|Section(ranges)| ranges.iter().map(|SectionRange(range)| range))
+        .collect::<Vec<_>>();
+    let runs = depth_runs(&ranges);
+
+    println!("Day 04 coverage");
+
+    let assigned = runs
+        .iter()
+        .filter(|(_, depth)| *depth > 0)
+        .map(|(run, _)| *run)
+        .collect::<IntervalSet<_>>();
+    let span = ranges
+        .iter()
+        .map(|range| **range)
+        .reduce(|span, range| {
+            Interval::new(span.start().min(range.start()), span.end().max(range.end()))
+        })
+        .unwrap();
+    let unassigned = IntervalSet::from_iter([span]).difference(&assigned);
+
+    println!("\tSpan: {span}");
+    match unassigned.is_empty() {
+        true => println!("\tUnassigned: none"),
+        false => println!(
+            "\tUnassigned: {} sections at {}",
+            unassigned.len(),
+            unassigned.intervals().join(", ")
+        ),
+    }
+
+    let max_depth = runs.iter().map(|(_, depth)| *depth).max().unwrap();
+    let deepest = runs
+        .iter()
+        .filter(|(_, depth)| *depth == max_depth)
+        .map(|(run, _)| *run)
+        .collect::<IntervalSet<_>>();
+    println!(
+        "\tMaximum depth: {max_depth} elves at {}",
+        deepest.intervals().join(", ")
+    );
+
+    // An elf is redundant when every one of its sections has at least one other elf.
+    println!("\tRedundant elves:");
+    for (line, Section(ranges)) in sections.iter().enumerate() {
+        for (elf, SectionRange(range)) in ranges.iter().enumerate() {
+            let first = runs.partition_point(|(run, _)| run.end() < range.start());
+            let redundant = runs[first..]
+                .iter()
+                .take_while(|(run, _)| run.start() <= range.end())
+                .all(|(_, depth)| *depth >= 2);
+
+            if redundant {
+                println!("\t\tline {:>4}, elf {}: {range}", line + 1, elf + 1);
+            }
+        }
+    }
+}
+
+static MAX_PERMUTED_ELVES: usize = 8;
+
+// Shrinks the ranges, laid out left to right in `order`, so that none overlap while
+// keeping as many sections as possible. Each elf's cut point only matters relative to
+// its own end, its trimmed start and the next elf's start.
+fn trim_in_order(ranges: &[Interval<u64>], order: &[usize]) -> Option<(u128, Vec<Interval<u64>>)> {
+    let mut states: HashMap<Option<u64>, (u128, Vec<Interval<u64>>)> =
+        HashMap::from([(None, (0, vec![]))]);
+
+    for (position, elf) in order.iter().enumerate() {
+        let range = ranges[*elf];
+        let next_start = order.get(position + 1).map(|next| ranges[*next].start());
+        let mut next_states: HashMap<Option<u64>, (u128, Vec<Interval<u64>>)> = HashMap::new();
+
+        for (last_end, (kept, trimmed)) in &states {
+            let start = match last_end {
+                Some(end) => match end.checked_add(1) {
+                    Some(after) => range.start().max(after),
+                    None => continue,
+                },
+                None => range.start(),
+            };
+
+            if start > range.end() {
+                continue;
+            }
+
+            let cuts = [
+                Some(range.end()),
+                Some(start),
+                next_start.and_then(|next| next.checked_sub(1)),
+            ];
+
+            for end in cuts
+                .into_iter()
+                .flatten()
+                .filter(|end| (start..=range.end()).contains(end))
+            {
+                let piece = Interval::new(start, end);
+                let kept = kept + piece.len();
+
+                if next_states
+                    .get(&Some(end))
+                    .is_none_or(|(best, _)| kept > *best)
+                {
+                    let mut trimmed = trimmed.clone();
+                    trimmed.push(piece);
+                    next_states.insert(Some(end), (kept, trimmed));
+                }
+            }
+        }
+
+        states = next_states;
+    }
+
+    let (kept, trimmed) = states.into_values().max_by_key(|(kept, _)| *kept)?;
+
+    let mut by_elf = vec![None; ranges.len()];
+    for (elf, piece) in order.iter().zip(trimmed) {
+        by_elf[*elf] = Some(piece);
+    }
+
+    Some((kept, by_elf.into_iter().flatten().collect()))
+}
+
+// Tries every left-to-right order for a handful of elves; larger sets are laid out by
+// range end, which is a heuristic rather than a guaranteed optimum. The flag tells
+// which of the two produced the plan.
+fn remove_overlaps(ranges: &[Interval<u64>]) -> Option<(Vec<Interval<u64>>, bool)> {
+    let elves = (0..ranges.len()).collect::<Vec<_>>();
+    let exact = ranges.len() <= MAX_PERMUTED_ELVES;
+
+    let (_, trimmed) = match ranges.len() {
+        n if n <= MAX_PERMUTED_ELVES => elves
+            .iter()
+            .copied()
+            .permutations(n)
+            .filter_map(|order| trim_in_order(ranges, &order))
+            .max_by_key(|(kept, _)| *kept)?,
+        _ => {
+            let order = elves
+                .into_iter()
+                .sorted_by_key(|elf| (ranges[*elf].end(), ranges[*elf].start()))
+                .collect::<Vec<_>>();
+            trim_in_order(ranges, &order)?
+        }
+    };
+
+    Some((trimmed, exact))
+}
+
+// Ranges that cannot keep a section of their own. Taking ranges by end and giving each
+// the first free section inside it keeps as many ranges as possible, so whatever is
+// left over is what makes a disjoint plan impossible.
+fn unplaceable(ranges: &[Interval<u64>]) -> Vec<usize> {
+    let mut next_free: HashMap<u64, Option<u64>> = HashMap::new();
+    let mut left_over = vec![];
+
+    for elf in (0..ranges.len()).sorted_by_key(|elf| (ranges[*elf].end(), ranges[*elf].start())) {
+        let mut skipped = vec![];
+        let mut free = Some(ranges[elf].start());
+
+        while let Some(Some(next)) = free.map(|section| next_free.get(&section).copied()) {
+            skipped.push(free.unwrap());
+            free = next;
+        }
+        for section in skipped {
+            next_free.insert(section, free);
+        }
+
+        match free.filter(|section| *section <= ranges[elf].end()) {
+            Some(section) => {
+                next_free.insert(section, section.checked_add(1));
+            }
+            None => left_over.push(elf),
+        }
+    }
+
+    left_over.sort();
+    left_over
+}
+
+fn rebalance(input: &str, global: bool) {
+    let sections = input
+        .lines()
+        .map(|line| {
+            let Section(ranges) = Section::from_raw(line);
+            ranges
+                .into_iter()
+                .map(|SectionRange(range)| range)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    println!(
+        "Day 04 rebalance ({})",
+        if global { "global" } else { "per line" }
+    );
+
+    let mut heuristic = false;
+    let per_line = |ranges: &Vec<Interval<u64>>, heuristic: &mut bool| match remove_overlaps(ranges)
+    {
+        Some((trimmed, exact)) => {
+            *heuristic |= !exact;
+            trimmed.into_iter().map(Some).collect()
+        }
+        None => vec![None; ranges.len()],
+    };
+
+    let all_ranges = sections.concat();
+    let plan = match global {
+        true => match remove_overlaps(&all_ranges) {
+            Some((trimmed, exact)) => {
+                heuristic |= !exact;
+                let mut trimmed = trimmed.into_iter();
+                sections
+                    .iter()
+                    .map(|ranges| {
+                        trimmed
+                            .by_ref()
+                            .take(ranges.len())
+                            .map(Some)
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>()
+            }
+            None => {
+                let owners = sections
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(line, ranges)| (0..ranges.len()).map(move |elf| (line, elf)))
+                    .collect::<Vec<_>>();
+                let left_over = unplaceable(&all_ranges);
+
+                println!(
+                    "\tNo global plan: {} ranges cannot keep a section of their own",
+                    left_over.len()
+                );
+                for idx in left_over {
+                    let (line, elf) = owners[idx];
+                    println!(
+                        "\t\tline {:>4}, elf {}: {}",
+                        line + 1,
+                        elf + 1,
+                        all_ranges[idx]
+                    );
+                }
+                println!("\tFalling back to per-line plans");
+
+                sections
+                    .iter()
+                    .map(|ranges| per_line(ranges, &mut heuristic))
+                    .collect()
+            }
+        },
+        false => sections
+            .iter()
+            .map(|ranges| per_line(ranges, &mut heuristic))
+            .collect::<Vec<_>>(),
+    };
+
+    let mut reassigned = 0;
+
+    for (line, (before, after)) in sections.iter().zip(&plan).enumerate() {
+        let before_text = before.iter().join(",");
+
+        match after.iter().all(Option::is_some) {
+            true => {
+                let after = after.iter().flatten().collect::<Vec<_>>();
+                let moved = before.iter().map(Interval::len).sum::<u128>()
+                    - after.iter().map(|range| range.len()).sum::<u128>();
+                reassigned += moved;
+                println!(
+                    "\tline {:>4}: {before_text} -> {} ({moved} reassigned)",
+                    line + 1,
+                    after.iter().join(",")
+                );
+            }
+            false => println!("\tline {:>4}: {before_text} -> no plan", line + 1),
+        }
+    }
+
+    match plan.iter().flatten().all(Option::is_some) {
+        true => println!("\tReassigned sections: {reassigned}"),
+        false => println!(
+            "\tReassigned sections: {reassigned} (some ranges cannot be kept non-empty and disjoint)"
+        ),
+    }
+    if heuristic {
+        println!(
+            "\tPlans with more than {MAX_PERMUTED_ELVES} ranges lay them out by end, so they may not be minimal"
+        );
+    }
+}
+
 pub(crate) fn run() {
     let input = include_str!("../input/04.txt");
 
@@ -72,3 +393,18 @@ pub(crate) fn run() {
     println!("\tPart 1: {}", part_1(input));
     println!("\tPart 2: {}", part_2(input));
 }
+
+pub(crate) fn run_with(args: &Args) {
+    let input = args.input(include_str!("../input/04.txt"));
+
+    match args.mode() {
+        Some("coverage") => coverage(&input),
+        Some("rebalance") => rebalance(&input, args.flag("global")),
+        Some(mode) => panic!("unknown day 04 mode: {mode}"),
+        None => {
+            println!("Day 04");
+            println!("\tPart 1: {}", part_1(&input));
+            println!("\tPart 2: {}", part_2(&input));
+        }
+    }
+}
@@ -1,37 +1,138 @@
-use std::collections::VecDeque;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    fmt::Display,
+    io::{self, BufRead},
+};
+
+use crate::cli::Args;
+
+// How a crane lands the crates of one move. `lifted` arrives bottom to top, exactly as
+// it sat on the source stack, and is returned in the order it ends up on the destination.
+trait Crane {
+    fn name(&self) -> String;
+    fn land(&self, lifted: Vec<Crate>) -> Vec<Crate>;
+    fn lifts(&self, count: usize) -> usize;
+
+    // Puts landed crates back in the order they were lifted. Every crane only permutes
+    // what it lifts, so landing a run of placeholder ids shows where each crate went.
+    fn unland(&self, landed: Vec<Crate>) -> Vec<Crate> {
+        let placement = self.land((0..landed.len() as u32).map(Crate).collect());
+        let mut lifted = landed.clone();
+
+        for (Crate(origin), crate_) in placement.into_iter().zip(landed) {
+            lifted[origin as usize] = crate_;
+        }
 
-#[derive(Debug)]
-struct Pop {
-    from: usize,
-    count: u8,
+        lifted
+    }
 }
 
-#[derive(Debug)]
-struct Push {
-    from: usize,
-    count: u8,
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_owned()
+    }
+
+    fn land(&self, mut lifted: Vec<Crate>) -> Vec<Crate> {
+        lifted.reverse();
+        lifted
+    }
+
+    fn lifts(&self, count: usize) -> usize {
+        count
+    }
 }
 
-#[derive(Debug)]
-enum Operation {
-    Push(Push),
-    Pop(Pop),
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_owned()
+    }
+
+    fn land(&self, lifted: Vec<Crate>) -> Vec<Crate> {
+        lifted
+    }
+
+    fn lifts(&self, count: usize) -> usize {
+        count.min(1)
+    }
+}
+
+// Lifts up to `capacity` crates at a time, keeping each lift in order.
+struct CapacityCrane(usize);
+
+impl Crane for CapacityCrane {
+    fn name(&self) -> String {
+        format!("capacity {} crane", self.0)
+    }
+
+    fn land(&self, lifted: Vec<Crate>) -> Vec<Crate> {
+        lifted.rchunks(self.0).flatten().copied().collect()
+    }
+
+    fn lifts(&self, count: usize) -> usize {
+        count.div_ceil(self.0)
+    }
 }
 
-enum CraneKind {
-    Single,
-    Multiple,
+// Lifts up to `capacity` crates at a time and turns each lift upside down. However the
+// move is split, the crates land in the CrateMover 9000's order; only the lift count
+// differs.
+struct FlippingCrane(usize);
+
+impl Crane for FlippingCrane {
+    fn name(&self) -> String {
+        format!("flipping capacity {} crane", self.0)
+    }
+
+    fn land(&self, lifted: Vec<Crate>) -> Vec<Crate> {
+        lifted
+            .rchunks(self.0)
+            .flat_map(|lift| lift.iter().rev())
+            .copied()
+            .collect()
+    }
+
+    fn lifts(&self, count: usize) -> usize {
+        count.div_ceil(self.0)
+    }
+}
+
+fn crane_from_arg(arg: &str) -> Box<dyn Crane> {
+    let capacity = |raw: &str| match raw.parse::<usize>() {
+        Ok(capacity) if capacity > 0 => capacity,
+        _ => panic!("invalid crane capacity: {raw}"),
+    };
+
+    match arg.split_once(':') {
+        None if arg == "9000" => Box::new(CrateMover9000),
+        None if arg == "9001" => Box::new(CrateMover9001),
+        Some(("capacity", raw)) => Box::new(CapacityCrane(capacity(raw))),
+        Some(("flip", raw)) => Box::new(FlippingCrane(capacity(raw))),
+        _ => panic!("unknown crane: {arg} (expected 9000, 9001, capacity:K or flip:K)"),
+    }
+}
+
+#[derive(Debug)]
+struct Operation {
+    line: usize,
+    count: usize,
+    from: usize,
+    to: usize,
 }
 
 impl Operation {
-    fn from_str(input: &str, kind: &CraneKind) -> VecDeque<Self> {
+    fn from_str(line: usize, input: &str) -> Self {
         let mut raw_instruction = input.split(' ');
 
         let _move = raw_instruction.next().unwrap();
 
         let move_count = raw_instruction
             .next()
-            .map(|n| n.parse::<u8>().unwrap())
+            .map(|n| n.parse::<usize>().unwrap())
             .unwrap();
 
         let _from = raw_instruction.next().unwrap();
@@ -48,188 +149,536 @@ impl Operation {
             .map(|n| n.parse::<usize>().unwrap())
             .unwrap();
 
-        let mut operations = VecDeque::new();
-
-        match kind {
-            CraneKind::Single => {
-                for _ in 0..move_count {
-                    operations.push_back(Operation::Pop(Pop {
-                        from: from_crate - 1,
-                        count: 1,
-                    }));
-                }
-
-                for _ in 0..move_count {
-                    operations.push_back(Operation::Push(Push {
-                        from: to_crate - 1,
-                        count: 1,
-                    }));
-                }
-            }
-            CraneKind::Multiple => {
-                operations.push_back(Operation::Pop(Pop {
-                    from: from_crate - 1,
-                    count: move_count,
-                }));
-
-                operations.push_back(Operation::Push(Push {
-                    from: to_crate - 1,
-                    count: move_count,
-                }));
-            }
+        Self {
+            line,
+            count: move_count,
+            from: from_crate,
+            to: to_crate,
         }
+    }
+}
 
-        operations
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from, self.to)
     }
 }
 
+// Index into `Stacks::names`, so moving crates around never copies their names.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Crate(u32);
+
+// Crates bottom to top, so a move only ever touches the end of the vector.
 #[derive(Debug)]
-struct Stack(VecDeque<u8>);
+struct Stack {
+    label: usize,
+    crates: Vec<Crate>,
+}
 
 impl Stack {
-    fn new() -> Self {
-        Self(VecDeque::new())
+    fn new(label: usize) -> Self {
+        Self {
+            label,
+            crates: Vec::new(),
+        }
     }
 
-    fn push_front(&mut self, item: u8) {
-        self.0.push_front(item)
+    fn top(&self) -> Option<&Crate> {
+        self.crates.last()
     }
+}
 
-    fn push_back(&mut self, item: u8) {
-        self.0.push_back(item)
-    }
+#[derive(Debug)]
+enum CraneError {
+    UnknownStack {
+        line: usize,
+        stack: usize,
+    },
+    NotEnoughCrates {
+        line: usize,
+        stack: usize,
+        requested: usize,
+        available: usize,
+    },
+}
 
-    fn pop_front(&mut self) -> Option<u8> {
-        self.0.pop_front()
+impl Display for CraneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CraneError::UnknownStack { line, stack } => {
+                write!(f, "line {line}: there is no stack {stack}")
+            }
+            CraneError::NotEnoughCrates {
+                line,
+                stack,
+                requested,
+                available,
+            } => write!(
+                f,
+                "line {line}: moving {requested} crates from stack {stack} which holds {available}, {} short",
+                requested - available
+            ),
+        }
     }
 }
 
-struct Stacks(Vec<Stack>);
+struct Stacks {
+    stacks: Vec<Stack>,
+    names: Vec<String>,
+}
 
 impl Stacks {
-    fn build_from_lines(parsed_lines: &VecDeque<VecDeque<Option<u8>>>) -> Self {
-        let mut stacks = std::iter::repeat_with(Stack::new)
-            .take(parsed_lines.len() + 1)
+    // The label row fixes each stack's column; every `[crate]` above it belongs to the
+    // label whose centre is nearest, so ragged lines and wide labels still line up.
+    fn from_drawing(drawing: &[&str]) -> Self {
+        let (label_row, crate_rows) = drawing.split_last().expect("drawing has a label row");
+
+        let labels = tokens(label_row);
+        let mut stacks = labels
+            .iter()
+            .map(|(_, label)| Stack::new(label.parse().expect("stack label parse")))
             .collect::<Vec<_>>();
-
-        for line in parsed_lines {
-            for (idx, item) in line.iter().enumerate() {
-                if let Some(item) = item {
-                    stacks[idx].push_back(*item);
-                }
+        let mut names = vec![];
+        let mut interned = HashMap::new();
+
+        for row in crate_rows.iter().rev() {
+            for (centre, token) in tokens(row) {
+                let name = token
+                    .strip_prefix('[')
+                    .and_then(|token| token.strip_suffix(']'))
+                    .unwrap_or_else(|| panic!("malformed crate: {token}"));
+
+                let right = labels.partition_point(|(label, _)| *label < centre);
+                let column = [right.checked_sub(1), Some(right)]
+                    .into_iter()
+                    .flatten()
+                    .filter(|idx| *idx < labels.len())
+                    .min_by_key(|idx| labels[*idx].0.abs_diff(centre))
+                    .unwrap();
+
+                let id = *interned.entry(name).or_insert_with(|| {
+                    names.push(name.to_owned());
+                    Crate(names.len() as u32 - 1)
+                });
+
+                stacks[column].crates.push(id);
             }
         }
 
-        Self(stacks)
+        Self { stacks, names }
     }
 
-    fn run_operations(&mut self, operations: &VecDeque<Operation>) {
-        let mut stack_pointer: VecDeque<u8> = VecDeque::new();
+    fn name(&self, Crate(id): &Crate) -> &str {
+        &self.names[*id as usize]
+    }
 
-        for operation in operations {
-            match operation {
-                Operation::Push(Push {
-                    from: idx,
-                    count: n,
-                }) => {
-                    for _ in 0..*n {
-                        if let Some(sp) = stack_pointer.pop_back() {
-                            self.0[*idx].push_front(sp);
-                        }
-                    }
-                }
-                Operation::Pop(Pop {
-                    from: idx,
-                    count: 1,
-                }) => {
-                    if let Some(sp) = self.0[*idx].pop_front() {
-                        stack_pointer.push_front(sp);
-                    }
-                }
-                Operation::Pop(Pop {
-                    from: idx,
-                    count: n,
-                }) => {
-                    let mut temp = vec![];
-
-                    for _ in 0..*n {
-                        if let Some(sp) = self.0[*idx].pop_front() {
-                            temp.push(sp);
-                        }
-                    }
+    fn index_of(&self, label: usize) -> Option<usize> {
+        match self.stacks.get(label.wrapping_sub(1)) {
+            Some(stack) if stack.label == label => Some(label - 1),
+            _ => self.stacks.iter().position(|stack| stack.label == label),
+        }
+    }
 
-                    temp.reverse();
-                    temp.iter().for_each(|item| stack_pointer.push_front(*item));
-                }
-            }
+    // Takes the top `count` crates off the stack labelled `label`, bottom to top.
+    fn take(&mut self, line: usize, label: usize, count: usize) -> Result<Vec<Crate>, CraneError> {
+        let idx = self.stack_index(line, label)?;
+        let available = self.stacks[idx].crates.len();
+
+        if available < count {
+            return Err(CraneError::NotEnoughCrates {
+                line,
+                stack: label,
+                requested: count,
+                available,
+            });
         }
+
+        Ok(self.stacks[idx].crates.split_off(available - count))
     }
 
-    fn top_of_stacks(&self) -> String {
-        let mut top = String::new();
+    fn stack_index(&self, line: usize, stack: usize) -> Result<usize, CraneError> {
+        self.index_of(stack)
+            .ok_or(CraneError::UnknownStack { line, stack })
+    }
 
-        for stack in &self.0 {
-            let item = stack.0[0];
-            top.push(char::from_u32(item as u32).unwrap());
-        }
+    fn execute(&mut self, operation: &Operation, crane: &dyn Crane) -> Result<(), CraneError> {
+        let to = self.stack_index(operation.line, operation.to)?;
+        let lifted = self.take(operation.line, operation.from, operation.count)?;
 
-        top
+        // Crates put back on the stack they came from end up exactly where they were,
+        // however the crane would have reordered them.
+        let mut landed = match operation.from == operation.to {
+            true => lifted,
+            false => crane.land(lifted),
+        };
+        self.stacks[to].crates.append(&mut landed);
+
+        Ok(())
     }
-}
 
-fn parse_lines(stack: &[&str]) -> VecDeque<VecDeque<Option<u8>>> {
-    let mut parsed_lines = stack
-        .iter()
-        .map(|line| {
-            line.as_bytes()
-                .chunks(4)
-                .map(|chunk| {
-                    let mut new_chunk = chunk;
-
-                    if chunk.len() == 4 {
-                        new_chunk = &new_chunk[..3];
-                    }
+    fn undo(&mut self, operation: &Operation, crane: &dyn Crane) -> Result<(), CraneError> {
+        let from = self.stack_index(operation.line, operation.from)?;
+        let landed = self.take(operation.line, operation.to, operation.count)?;
 
-                    match &new_chunk[1] {
-                        n if n.is_ascii_alphabetic() => Some(*n),
-                        _ => None,
-                    }
-                })
-                .collect::<VecDeque<_>>()
-        })
-        .collect::<VecDeque<_>>();
+        let mut lifted = match operation.from == operation.to {
+            true => landed,
+            false => crane.unland(landed),
+        };
+        self.stacks[from].crates.append(&mut lifted);
 
-    parsed_lines.pop_back();
+        Ok(())
+    }
 
-    parsed_lines
+    fn run_operations(
+        &mut self,
+        operations: &[Operation],
+        crane: &dyn Crane,
+    ) -> Result<(), CraneError> {
+        operations
+            .iter()
+            .try_for_each(|operation| self.execute(operation, crane))
+    }
+
+    // Treats the current stacks as the result of `operations` and winds them back to the
+    // drawing they started from.
+    fn reverse_operations(
+        &mut self,
+        operations: &[Operation],
+        crane: &dyn Crane,
+    ) -> Result<(), CraneError> {
+        operations
+            .iter()
+            .rev()
+            .try_for_each(|operation| self.undo(operation, crane))
+    }
+
+    // Renders the drawing in the puzzle's own layout; highlighted columns are wrapped in
+    // the given ANSI colour codes.
+    fn render(&self, highlight: &[(usize, &str)]) -> String {
+        let widths = self
+            .stacks
+            .iter()
+            .map(|stack| {
+                let widest_crate = stack.crates.iter().map(|c| self.name(c).len() + 2).max();
+                widest_crate.unwrap_or(3).max(stack.label.to_string().len())
+            })
+            .collect::<Vec<_>>();
+        let height = self
+            .stacks
+            .iter()
+            .map(|stack| stack.crates.len())
+            .max()
+            .unwrap_or(0);
+
+        let cell = |idx: usize, text: &str| {
+            let padding = widths[idx] - text.len();
+            let left = padding.div_ceil(2);
+            let cell = format!("{}{text}{}", " ".repeat(left), " ".repeat(padding - left));
+
+            match highlight.iter().find(|(column, _)| *column == idx) {
+                Some((_, colour)) if !text.is_empty() => format!("\x1b[{colour}m{cell}\x1b[0m"),
+                _ => cell,
+            }
+        };
+
+        let mut rows = (0..height)
+            .rev()
+            .map(|level| {
+                self.stacks
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, stack)| match stack.crates.get(level) {
+                        Some(c) => cell(idx, &format!("[{}]", self.name(c))),
+                        None => cell(idx, ""),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>();
+
+        rows.push(
+            self.stacks
+                .iter()
+                .enumerate()
+                .map(|(idx, stack)| cell(idx, &stack.label.to_string()))
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+
+        rows.join("\n")
+    }
+
+    fn top_of_stacks(&self) -> String {
+        self.stacks
+            .iter()
+            .map(|stack| stack.top().map_or(" ", |top| self.name(top)))
+            .collect()
+    }
+}
+
+impl Display for Stacks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(&[]))
+    }
 }
 
-fn expand_top_of_stacks(input: &str, crane_kind: CraneKind) -> String {
+// Whitespace-separated tokens paired with the doubled byte offset of their centre.
+fn tokens(line: &str) -> Vec<(usize, &str)> {
+    let mut tokens = vec![];
+    let mut start = None;
+
+    for (idx, c) in line.char_indices().chain([(line.len(), ' ')]) {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(idx),
+            (Some(begin), true) => {
+                tokens.push((begin + idx - 1, &line[begin..idx]));
+                start = None;
+            }
+            _ => (),
+        }
+    }
+
+    tokens
+}
+
+fn parse(input: &str) -> (Stacks, Vec<Operation>) {
     let raw_stack_lines = input
         .lines()
         .take_while(|line| !line.is_empty())
         .collect::<Vec<_>>();
 
-    let parsed_lines = parse_lines(&raw_stack_lines);
-    let mut stacks = Stacks::build_from_lines(&parsed_lines);
+    let stacks = Stacks::from_drawing(&raw_stack_lines);
 
     let operations = input
         .lines()
+        .enumerate()
         .skip(raw_stack_lines.len() + 1)
-        .flat_map(|line| Operation::from_str(line, &crane_kind))
-        .collect::<VecDeque<_>>();
+        .map(|(idx, line)| Operation::from_str(idx + 1, line))
+        .collect();
+
+    (stacks, operations)
+}
+
+fn expand_top_of_stacks(input: &str, crane: &dyn Crane) -> Result<String, CraneError> {
+    let (mut stacks, operations) = parse(input);
+
+    stacks.run_operations(&operations, crane)?;
+
+    Ok(stacks.top_of_stacks())
+}
+
+// The drawing after running every operation, or before them when `reverse` is set, in
+// which case the input's drawing is taken to be the final one.
+fn redraw(input: &str, crane: &dyn Crane, reverse: bool) -> Result<Stacks, CraneError> {
+    let (mut stacks, operations) = parse(input);
+
+    match reverse {
+        true => stacks.reverse_operations(&operations, crane)?,
+        false => stacks.run_operations(&operations, crane)?,
+    }
+
+    Ok(stacks)
+}
+
+// Several identical cranes work through the operations in rounds. A round takes the
+// longest prefix of pending operations that touch pairwise disjoint stacks, one per
+// crane, and lasts as many lifts as its slowest move.
+fn run_in_parallel(
+    input: &str,
+    crane: &dyn Crane,
+    cranes: usize,
+) -> Result<(String, usize, usize), CraneError> {
+    let (mut stacks, operations) = parse(input);
+    let (mut rounds, mut lifts) = (0, 0);
+    let mut pending = operations.as_slice();
+
+    while !pending.is_empty() {
+        let mut busy: Vec<usize> = vec![];
+        let round = pending
+            .iter()
+            .take(cranes)
+            .take_while(|operation| {
+                let free = !busy.contains(&operation.from) && !busy.contains(&operation.to);
+                busy.extend([operation.from, operation.to]);
+                free
+            })
+            .count();
+
+        for operation in &pending[..round] {
+            stacks.execute(operation, crane)?;
+        }
 
-    stacks.run_operations(&operations);
+        rounds += 1;
+        lifts += pending[..round]
+            .iter()
+            .map(|operation| crane.lifts(operation.count))
+            .max()
+            .unwrap_or(0);
+        pending = &pending[round..];
+    }
 
-    stacks.top_of_stacks()
+    Ok((stacks.top_of_stacks(), rounds, lifts))
 }
 
 fn part_1(input: &str) -> String {
-    expand_top_of_stacks(input, CraneKind::Single)
+    expand_top_of_stacks(input, &CrateMover9000).unwrap()
 }
 
 fn part_2(input: &str) -> String {
-    expand_top_of_stacks(input, CraneKind::Multiple)
+    expand_top_of_stacks(input, &CrateMover9001).unwrap()
+}
+
+static DEFAULT_SEARCH_BUDGET: usize = 100_000;
+
+// A* over whole stack configurations, giving up once `budget` of them have been seen. A move
+// changes at most two tops, so half the wrong tops, rounded up, never overestimates the
+// moves left; targets that don't name one crate per stack fall back to plain BFS.
+fn plan_moves(
+    stacks: &Stacks,
+    target: &str,
+    crane: &dyn Crane,
+    budget: usize,
+) -> Option<Vec<Operation>> {
+    let top = |crates: &Vec<Crate>| crates.last().map_or(" ", |top| stacks.name(top));
+    let wanted = target.chars().map(String::from).collect::<Vec<_>>();
+    let estimate = |state: &[Vec<Crate>]| match wanted.len() == state.len() {
+        true => state
+            .iter()
+            .zip(&wanted)
+            .filter(|(crates, wanted)| top(crates) != wanted.as_str())
+            .count()
+            .div_ceil(2),
+        false => 0,
+    };
+
+    let start = stacks
+        .stacks
+        .iter()
+        .map(|stack| stack.crates.clone())
+        .collect::<Vec<_>>();
+    let mut frontier = BinaryHeap::from([Reverse((estimate(&start), 0, 0))]);
+    let mut index = HashMap::from([(start.clone(), 0)]);
+    let mut states = vec![(start, 0, None)];
+
+    while let Some(Reverse((_, moves, idx))) = frontier.pop() {
+        let (state, best, _) = &states[idx];
+        if moves > *best {
+            continue;
+        }
+
+        if state.iter().map(top).collect::<String>() == target {
+            let mut plan: Vec<(usize, usize, usize)> = vec![];
+            let mut current = idx;
+
+            while let Some((parent, (from, to, count))) = states[current].2 {
+                plan.push((from, to, count));
+                current = parent;
+            }
+
+            return Some(
+                plan.into_iter()
+                    .rev()
+                    .enumerate()
+                    .map(|(line, (from, to, count))| Operation {
+                        line: line + 1,
+                        count,
+                        from: stacks.stacks[from].label,
+                        to: stacks.stacks[to].label,
+                    })
+                    .collect(),
+            );
+        }
+
+        let state = state.clone();
+        for (from, to) in
+            (0..state.len()).flat_map(|from| (0..state.len()).map(move |to| (from, to)))
+        {
+            if from == to {
+                continue;
+            }
+
+            for count in 1..=state[from].len() {
+                let mut next = state.clone();
+                let lifted = next[from].split_off(state[from].len() - count);
+                next[to].extend(crane.land(lifted));
+
+                let step = Some((idx, (from, to, count)));
+                let next_idx = match index.get(&next) {
+                    Some(&seen) if states[seen].1 <= moves + 1 => continue,
+                    Some(&seen) => {
+                        states[seen].1 = moves + 1;
+                        states[seen].2 = step;
+                        seen
+                    }
+                    None if states.len() >= budget => return None,
+                    None => {
+                        index.insert(next.clone(), states.len());
+                        states.push((next, moves + 1, step));
+                        states.len() - 1
+                    }
+                };
+
+                let estimate = estimate(&states[next_idx].0);
+                frontier.push(Reverse((moves + 1 + estimate, moves + 1, next_idx)));
+            }
+        }
+    }
+
+    None
+}
+
+static SOURCE_COLOUR: &str = "1;31";
+static DESTINATION_COLOUR: &str = "1;32";
+
+// While stepping, an empty line runs the next operation (redoing it if it was undone),
+// `u` undoes the last one and `q` stops.
+fn animate(input: &str, crane: &dyn Crane, step: bool) -> Result<String, CraneError> {
+    let (mut stacks, operations) = parse(input);
+    let mut stdin = io::stdin().lock();
+    let mut done: usize = 0;
+
+    println!("{stacks}\n");
+
+    loop {
+        let mut command = String::new();
+        if step {
+            stdin.read_line(&mut command).expect("read from stdin");
+        }
+
+        let (verb, operation, source, destination) = match command.trim() {
+            "q" => break,
+            "u" => {
+                let Some(previous) = done.checked_sub(1) else {
+                    println!("nothing to undo\n");
+                    continue;
+                };
+                let operation = &operations[previous];
+
+                stacks.undo(operation, crane)?;
+                done = previous;
+                ("undo ", operation, operation.to, operation.from)
+            }
+            _ => {
+                let Some(operation) = operations.get(done) else {
+                    break;
+                };
+
+                stacks.execute(operation, crane)?;
+                done += 1;
+                ("", operation, operation.from, operation.to)
+            }
+        };
+
+        let [source, destination] =
+            [source, destination].map(|label| stacks.index_of(label).unwrap());
+        println!("{verb}{operation} (line {})", operation.line);
+        println!(
+            "{}\n",
+            stacks.render(&[(source, SOURCE_COLOUR), (destination, DESTINATION_COLOUR)])
+        );
+    }
+
+    Ok(stacks.top_of_stacks())
 }
 
 pub(crate) fn run() {
@@ -239,3 +688,76 @@ pub(crate) fn run() {
     println!("\tPart 1: {}", part_1(input));
     println!("\tPart 2: {}", part_2(input));
 }
+
+pub(crate) fn run_with(args: &Args) {
+    let input = args.input(include_str!("../input/05.txt"));
+
+    let report = |result: Result<String, CraneError>| match result {
+        Ok(top) => top,
+        Err(e) => format!("error: {e}"),
+    };
+
+    let crane = crane_from_arg(&args.value::<String>("crane").unwrap_or("9000".to_owned()));
+
+    match args.mode() {
+        None if args.flag("animate") => {
+            println!(
+                "Top: {}",
+                report(animate(&input, crane.as_ref(), args.flag("step")))
+            )
+        }
+        Some("draw") if args.flag("after") => match redraw(&input, crane.as_ref(), false) {
+            Ok(stacks) => println!("{stacks}"),
+            Err(e) => println!("error: {e}"),
+        },
+        Some("draw") => println!("{}", parse(&input).0),
+        Some("reverse") => match redraw(&input, crane.as_ref(), true) {
+            Ok(stacks) => println!("{stacks}"),
+            Err(e) => println!("error: {e}"),
+        },
+        Some("plan") => {
+            let target = args
+                .value::<String>("target")
+                .expect("--target is required");
+            let budget = args.value("budget").unwrap_or(DEFAULT_SEARCH_BUDGET);
+
+            match plan_moves(&parse(&input).0, &target, crane.as_ref(), budget) {
+                Some(plan) => plan.iter().for_each(|operation| println!("{operation}")),
+                None => eprintln!("no plan for {target:?} within {budget} states"),
+            }
+        }
+        Some(mode) => panic!("unknown day 05 mode: {mode}"),
+        None if args.flag("cranes") => {
+            let cranes = args.value("cranes").expect("--cranes needs a count");
+            assert!(cranes >= 1, "--cranes must be at least 1");
+
+            println!("Day 05 ({cranes} x {})", crane.name());
+            match run_in_parallel(&input, crane.as_ref(), cranes) {
+                Ok((top, rounds, lifts)) => {
+                    println!("\tTop: {top}");
+                    println!("\tRounds: {rounds}");
+                    println!("\tLifts: {lifts}");
+                }
+                Err(e) => println!("\terror: {e}"),
+            }
+        }
+        None if args.flag("crane") => {
+            println!("Day 05 ({})", crane.name());
+            println!(
+                "\tTop: {}",
+                report(expand_top_of_stacks(&input, crane.as_ref()))
+            );
+        }
+        None => {
+            println!("Day 05");
+            println!(
+                "\tPart 1: {}",
+                report(expand_top_of_stacks(&input, &CrateMover9000))
+            );
+            println!(
+                "\tPart 2: {}",
+                report(expand_top_of_stacks(&input, &CrateMover9001))
+            );
+        }
+    }
+}
@@ -1,33 +1,375 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Write},
+};
+
 use itertools::Itertools;
+use rand::{rngs::StdRng, seq::IteratorRandom, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::cli::Args;
 
 enum Marker {
     StartOfPacket = 4,
     StartOfMessage = 14,
 }
 
-fn find_first_marker_pos(input: &str, marker: Marker) -> i32 {
-    input
-        .char_indices()
-        .collect::<Vec<_>>()
-        .as_slice()
-        .windows(marker as usize)
-        .find(|window| window.iter().map(|(_, c)| c).all_unique())
-        .map(|chars| chars.last().map(|(idx, _)| *idx as i32 + 1).unwrap_or(-1))
-        .unwrap_or(-1)
+static STREAM_BUFFER_SIZE: usize = 1 << 16;
+
+// The last `len` bytes in a ring buffer, with a count per byte value and the number of
+// values held more than once, so each new byte costs the same whatever the length.
+struct MarkerDetector {
+    window: Vec<u8>,
+    counts: [u32; 256],
+    repeated: usize,
+    seen: usize,
+}
+
+impl MarkerDetector {
+    fn new(len: usize) -> Self {
+        assert!(len > 0, "marker length must be positive");
+
+        Self {
+            window: vec![0; len],
+            counts: [0; 256],
+            repeated: 0,
+            seen: 0,
+        }
+    }
+
+    // Feeds the next byte; true when it completes a window of distinct bytes.
+    fn push(&mut self, byte: u8) -> bool {
+        let len = self.window.len();
+        let slot = self.seen % len;
+
+        if self.seen >= len {
+            let dropped = self.window[slot] as usize;
+            self.counts[dropped] -= 1;
+            if self.counts[dropped] == 1 {
+                self.repeated -= 1;
+            }
+        }
+
+        self.window[slot] = byte;
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.repeated += 1;
+        }
+
+        self.seen += 1;
+        self.seen >= len && self.repeated == 0
+    }
+}
+
+// Number of bytes read up to and including the first marker of length `len`.
+fn find_marker(bytes: impl IntoIterator<Item = u8>, len: usize) -> Option<usize> {
+    let mut detector = MarkerDetector::new(len);
+
+    bytes
+        .into_iter()
+        .position(|byte| detector.push(byte))
+        .map(|idx| idx + 1)
+}
+
+// Looks for markers of every given length in a single pass, stopping as soon as all of
+// them are found, so the stream never has to fit in memory.
+fn find_markers_in(mut reader: impl Read, lengths: &[usize]) -> io::Result<Vec<Option<usize>>> {
+    let mut detectors = lengths
+        .iter()
+        .map(|len| MarkerDetector::new(*len))
+        .collect::<Vec<_>>();
+    let mut found = vec![None; lengths.len()];
+    let mut buffer = vec![0; STREAM_BUFFER_SIZE];
+
+    while found.iter().any(Option::is_none) {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        for (detector, found) in detectors.iter_mut().zip(&mut found) {
+            if found.is_none() && buffer[..read].iter().any(|byte| detector.push(*byte)) {
+                *found = Some(detector.seen);
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+struct Frame {
+    marker_offset: usize,
+    payload_offset: usize,
+    payload: Vec<u8>,
+}
+
+// Splits a datastream at every marker of one length: a frame is the payload between the
+// end of one marker and the start of the next, or the end of the stream. Markers never
+// overlap and the bytes before the first one belong to no frame. A start-of-message
+// marker always contains start-of-packet markers, so each length frames the stream on
+// its own.
+struct Frames<I> {
+    bytes: I,
+    len: usize,
+    detector: MarkerDetector,
+    offset: usize,
+    open: Option<usize>,
+    buffer: Vec<u8>,
+}
+
+impl<I: Iterator<Item = u8>> Iterator for Frames<I> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for byte in self.bytes.by_ref() {
+            self.offset += 1;
+            self.buffer.push(byte);
+
+            if !self.detector.push(byte) {
+                continue;
+            }
+
+            self.detector = MarkerDetector::new(self.len);
+            self.buffer.truncate(self.buffer.len() - self.len);
+            let payload = std::mem::take(&mut self.buffer);
+
+            if let Some(payload_offset) = self.open.replace(self.offset) {
+                return Some(Frame {
+                    marker_offset: payload_offset - self.len,
+                    payload_offset,
+                    payload,
+                });
+            }
+        }
+
+        let payload_offset = self.open.take()?;
+        Some(Frame {
+            marker_offset: payload_offset - self.len,
+            payload_offset,
+            payload: std::mem::take(&mut self.buffer),
+        })
+    }
+}
+
+fn frames<I: IntoIterator<Item = u8>>(bytes: I, len: usize) -> Frames<I::IntoIter> {
+    Frames {
+        bytes: bytes.into_iter(),
+        len,
+        detector: MarkerDetector::new(len),
+        offset: 0,
+        open: None,
+        buffer: vec![],
+    }
 }
 
-fn part_1(input: &str) -> i32 {
-    find_first_marker_pos(input, Marker::StartOfPacket)
+static FRAME_PREVIEW_LEN: usize = 24;
+
+fn print_frames(bytes: impl Iterator<Item = u8>, len: usize) {
+    let mut count = 0;
+
+    println!("Day 06 frames (markers of length {len})");
+
+    for frame in frames(bytes, len) {
+        let preview =
+            String::from_utf8_lossy(&frame.payload[..frame.payload.len().min(FRAME_PREVIEW_LEN)]);
+        let ellipsis = if frame.payload.len() > FRAME_PREVIEW_LEN {
+            "..."
+        } else {
+            ""
+        };
+
+        count += 1;
+        println!(
+            "\tmarker at {:>6}, payload at {:>6}: {:>5} bytes {preview:?}{ellipsis}",
+            frame.marker_offset,
+            frame.payload_offset,
+            frame.payload.len()
+        );
+    }
+
+    println!("\tFrames: {count}");
 }
 
-fn part_2(input: &str) -> i32 {
-    find_first_marker_pos(input, Marker::StartOfMessage)
+static DEFAULT_ALPHABET: &str = "abcdefghijklmnopqrstuvwxyz";
+
+// A stream whose first start-of-packet and start-of-message markers end after exactly
+// `packet` and `message` bytes. A free byte that would complete a marker too early is
+// replaced by a copy of one of the bytes before it, and each planted marker opens by
+// repeating the byte in front of it, which spoils every window straddling the two. When
+// the start-of-message marker opens inside the start-of-packet one that byte is part of
+// the packet, so the byte after the packet repeats it instead.
+fn generate(size: usize, alphabet: &str, packet: usize, message: usize, seed: u64) -> Vec<u8> {
+    let alphabet = alphabet.bytes().unique().collect::<Vec<_>>();
+    let markers = [
+        (Marker::StartOfPacket as usize, packet),
+        (Marker::StartOfMessage as usize, message),
+    ];
+
+    assert!(
+        alphabet.len() >= Marker::StartOfMessage as usize,
+        "alphabet needs at least 14 distinct bytes"
+    );
+    for (len, end) in markers {
+        assert!(
+            (len..=size).contains(&end),
+            "a marker of length {len} cannot end at {end} in {size} bytes"
+        );
+    }
+    assert!(
+        packet + 10 <= message,
+        "every 4 bytes of a start-of-message marker form a start-of-packet marker, so the first \
+         start-of-packet marker must end at least 10 bytes before the start-of-message marker"
+    );
+
+    let packet_start = packet - Marker::StartOfPacket as usize;
+    let message_start = message - Marker::StartOfMessage as usize;
+    let straddles = packet_start < message_start && message_start < packet;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut stream = Vec::with_capacity(size);
+
+    for idx in 0..size {
+        let planted = markers
+            .iter()
+            .filter(|(len, end)| (end - len..*end).contains(&idx))
+            .collect::<Vec<_>>();
+
+        let byte = match planted.is_empty() {
+            false if straddles && idx == packet => stream[message_start - 1],
+            false
+                if idx > 0
+                    && !(straddles && idx == message_start)
+                    && planted.iter().any(|(len, end)| end - len == idx) =>
+            {
+                stream[idx - 1]
+            }
+            false => {
+                let taken = planted
+                    .iter()
+                    .flat_map(|(len, end)| &stream[end - len..idx])
+                    .collect::<Vec<_>>();
+
+                *alphabet
+                    .iter()
+                    .filter(|byte| !taken.contains(byte))
+                    .choose(&mut rng)
+                    .unwrap()
+            }
+            true => {
+                let candidate = alphabet[rng.gen_range(0..alphabet.len())];
+                let shortest_pending = markers
+                    .iter()
+                    .filter(|(len, end)| idx + 1 >= *len && idx + 1 < *end)
+                    .map(|(len, _)| *len)
+                    .min();
+
+                match shortest_pending {
+                    Some(len)
+                        if stream[idx + 1 - len..]
+                            .iter()
+                            .chain([&candidate])
+                            .all_unique() =>
+                    {
+                        *stream[idx + 1 - len..].choose(&mut rng).unwrap()
+                    }
+                    _ => candidate,
+                }
+            }
+        };
+
+        stream.push(byte);
+    }
+
+    stream
+}
+
+fn part_1(input: &str) -> Option<usize> {
+    find_marker(input.bytes(), Marker::StartOfPacket as usize)
+}
+
+fn part_2(input: &str) -> Option<usize> {
+    find_marker(input.bytes(), Marker::StartOfMessage as usize)
+}
+
+fn report(position: Option<usize>) -> String {
+    position.map_or("none".to_owned(), |position| position.to_string())
 }
 
 pub(crate) fn run() {
     let input = include_str!("../input/06.txt");
 
     println!("Day 06");
-    println!("\tPart 1: {}", part_1(input));
-    println!("\tPart 2: {}", part_2(input));
+    println!("\tPart 1: {}", report(part_1(input)));
+    println!("\tPart 2: {}", report(part_2(input)));
+}
+
+pub(crate) fn run_with(args: &Args) {
+    let markers = match args.value::<String>("length") {
+        Some(lengths) => lengths
+            .split(',')
+            .map(|len| {
+                let len = len.parse::<usize>().expect("marker length parse");
+                (format!("Length {len}"), len)
+            })
+            .collect::<Vec<_>>(),
+        None => vec![
+            ("Part 1".to_owned(), Marker::StartOfPacket as usize),
+            ("Part 2".to_owned(), Marker::StartOfMessage as usize),
+        ],
+    };
+    let lengths = markers.iter().map(|(_, len)| *len).collect::<Vec<_>>();
+
+    match args.mode() {
+        Some("frames") => {
+            for len in lengths {
+                match args.value::<String>("input") {
+                    Some(path) => {
+                        let file = File::open(&path).unwrap_or_else(|e| panic!("{path}: {e}"));
+                        let bytes = BufReader::new(file)
+                            .bytes()
+                            .map(|byte| byte.unwrap_or_else(|e| panic!("{path}: {e}")));
+                        print_frames(bytes, len);
+                    }
+                    None => print_frames(include_str!("../input/06.txt").bytes(), len),
+                }
+            }
+        }
+        Some("generate") => {
+            let packet = args.value("packet").expect("--packet is required");
+            let message = args.value("message").expect("--message is required");
+            let size = args.value("size").unwrap_or(message);
+            let alphabet = args
+                .value::<String>("alphabet")
+                .unwrap_or(DEFAULT_ALPHABET.to_owned());
+            let seed = args.value("seed").unwrap_or(2022);
+
+            let stream = generate(size, &alphabet, packet, message, seed);
+
+            match args.value::<String>("output") {
+                Some(path) => {
+                    std::fs::write(&path, &stream).unwrap_or_else(|e| panic!("{path}: {e}"));
+                    println!("Day 06 generated {size} bytes into {path} (seed {seed})");
+                    println!("\tPart 1: {packet}");
+                    println!("\tPart 2: {message}");
+                }
+                None => io::stdout().write_all(&stream).expect("write to stdout"),
+            }
+        }
+        Some(mode) => panic!("unknown day 06 mode: {mode}"),
+        None => {
+            let positions = match args.value::<String>("input") {
+                Some(path) => File::open(&path)
+                    .and_then(|file| find_markers_in(file, &lengths))
+                    .unwrap_or_else(|e| panic!("{path}: {e}")),
+                None => find_markers_in(include_str!("../input/06.txt").as_bytes(), &lengths)
+                    .expect("read embedded input"),
+            };
+
+            println!("Day 06");
+            for ((label, _), position) in markers.iter().zip(positions) {
+                println!("\t{label}: {}", report(position));
+            }
+        }
+    }
 }
@@ -1,9 +1,9 @@
-use std::collections::{HashMap, VecDeque};
+use std::{collections::HashMap, fmt::Display};
 
 use itertools::Itertools;
-use petgraph::{graph::NodeIndex, visit::DfsPostOrder, Direction, Graph};
 
-static SEP: &str = "~";
+use crate::cli::Args;
+
 static DISK_SIZE_THRESHOLD: u32 = 100000;
 static TOTAL_DISK_SIZE: u32 = 70000000;
 static SPACE_NEEDED: u32 = 30000000;
@@ -109,160 +109,305 @@ impl Terminal {
     }
 }
 
-type FileSystem = (Graph<String, u32>, Option<NodeIndex>);
-
-impl From<Terminal> for FileSystem {
-    fn from(terminal: Terminal) -> Self {
-        let mut g = Graph::<String, u32>::new();
-        let mut pwd: VecDeque<String> = VecDeque::new();
-        let mut root_node_index: Option<_> = None;
+type NodeId = usize;
 
-        for line in &terminal.0 {
-            match line {
-                TerminalLine::Command(Command::ChangeDirectory(dir)) => match dir {
-                    Directory::Back => {
-                        if pwd.is_empty() {
-                            continue;
-                        }
+static ROOT: NodeId = 0;
 
-                        pwd.pop_back();
-                    }
-                    Directory::Folder(dir) if dir == "/" => {
-                        let node_index = g.add_node(dir.to_owned());
+#[derive(Debug)]
+enum Node {
+    Directory {
+        name: String,
+        parent: Option<NodeId>,
+        children: HashMap<String, NodeId>,
+    },
+    File(u32),
+}
 
-                        pwd.push_front(dir.to_owned());
-                        root_node_index = Some(node_index);
-                    }
-                    Directory::Folder(dir) => {
-                        let full_path = pwd.iter().join(SEP);
-                        let new_full_path = full_path.clone() + SEP + dir;
-
-                        if !g.node_weights().any(|node| *node == new_full_path) {
-                            let to = g.add_node(new_full_path.to_owned());
-
-                            if !pwd.is_empty() && g.node_weights().any(|node| *node == full_path) {
-                                match g.node_indices().find(|node| g[*node] == *full_path) {
-                                    Some(from) => {
-                                        g.add_edge(from, to, 0);
-                                    }
-                                    None => unreachable!(),
-                                }
-                            }
-                        }
+#[derive(Debug)]
+enum Warning {
+    SizeMismatch {
+        path: String,
+        previous: u32,
+        listed: u32,
+    },
+    DirectoryListedAsFile {
+        path: String,
+    },
+    FileListedAsDirectory {
+        path: String,
+    },
+    NotADirectory {
+        path: String,
+    },
+    BackFromRoot,
+}
 
-                        pwd.push_back(dir.to_owned());
-                    }
-                },
-                TerminalLine::Output(output) => {
-                    for out in output {
-                        if let Output::File(filename, filesize) = out {
-                            let full_path = pwd.iter().join(SEP);
-                            if let Some(from) = g.node_indices().find(|node| g[*node] == *full_path)
-                            {
-                                let new_full_path = full_path.clone() + SEP + filename;
-                                if !g.node_weights().any(|node| *node == new_full_path) {
-                                    let to = g.add_node(new_full_path.to_owned());
-                                    g.add_edge(from, to, *filesize);
-                                }
-                            }
-                        };
-                    }
-                }
-                _ => (),
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::SizeMismatch {
+                path,
+                previous,
+                listed,
+            } => write!(
+                f,
+                "{path} listed as {listed} bytes after {previous}, keeping {listed}"
+            ),
+            Warning::DirectoryListedAsFile { path } => {
+                write!(
+                    f,
+                    "directory {path} later listed as a file, keeping the directory"
+                )
+            }
+            Warning::FileListedAsDirectory { path } => {
+                write!(
+                    f,
+                    "file {path} later listed as a directory, keeping the file"
+                )
+            }
+            Warning::NotADirectory { path } => {
+                write!(
+                    f,
+                    "cannot cd into file {path}, skipping listings until leaving it"
+                )
             }
+            Warning::BackFromRoot => write!(f, "cd .. at the root, staying put"),
         }
-
-        (g, root_node_index)
     }
 }
 
-fn calculate_dir_size(input: &str) -> HashMap<String, u32> {
-    let terminal = Terminal::parse_raw_lines(input);
+// Nodes live in one vector and refer to each other by index; the root is always first.
+#[derive(Debug)]
+struct FsTree(Vec<Node>);
+
+impl FsTree {
+    fn new() -> Self {
+        Self(vec![Node::Directory {
+            name: "/".to_owned(),
+            parent: None,
+            children: HashMap::new(),
+        }])
+    }
 
-    let (g, root_node_index) = FileSystem::from(terminal);
-    // println!("{}", Dot::with_config(&g, &[]));
+    fn parent(&self, dir: NodeId) -> Option<NodeId> {
+        match &self.0[dir] {
+            Node::Directory { parent, .. } => *parent,
+            Node::File(_) => unreachable!(),
+        }
+    }
+
+    fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        match &self.0[dir] {
+            Node::Directory { children, .. } => children.get(name).copied(),
+            Node::File(_) => unreachable!(),
+        }
+    }
 
-    let mut dir_sizes = HashMap::new();
+    fn path(&self, dir: NodeId, name: &str) -> String {
+        let mut parts = vec![name];
+        let mut node = Some(dir);
 
-    let mut dfs = DfsPostOrder::new(&g, root_node_index.unwrap());
+        while let Some(Node::Directory { name, parent, .. }) = node.map(|id| &self.0[id]) {
+            if parent.is_some() {
+                parts.push(name);
+            }
+            node = *parent;
+        }
 
-    while let Some(node) = dfs.next(&g) {
-        let mut edges = g.neighbors_directed(node, Direction::Incoming).detach();
+        format!("/{}", parts.iter().rev().join("/"))
+    }
 
-        while let Some(edge) = edges.next_edge(&g) {
-            if let Some(edge_weight) = g.edge_weight(edge) {
-                let (f_idx, t_idx) = g.edge_endpoints(edge).unwrap();
+    fn insert(&mut self, dir: NodeId, name: &str, node: Node) -> NodeId {
+        let id = self.0.len();
+        self.0.push(node);
 
-                let from = g[f_idx].clone();
-                let to = g[t_idx].clone();
+        match &mut self.0[dir] {
+            Node::Directory { children, .. } => children.insert(name.to_owned(), id),
+            Node::File(_) => unreachable!(),
+        };
 
-                if edge_weight == &0 {
-                    let sum_to = match dir_sizes.get(&to) {
-                        Some(v) => *v,
-                        None => {
-                            panic!(
-                                "No value found? from={} to={} weight={}",
-                                from, to, edge_weight
-                            );
-                        }
-                    };
+        id
+    }
 
-                    match dir_sizes.get_mut(&from) {
-                        Some(size_tally) => {
-                            *size_tally += sum_to;
-                        }
-                        None => {
-                            dir_sizes.insert(from, sum_to);
-                        }
-                    }
+    // The directory `name` inside `dir`, created if it wasn't seen yet; `None` when that
+    // name already belongs to a file.
+    fn directory(&mut self, dir: NodeId, name: &str) -> Option<NodeId> {
+        match self.child(dir, name) {
+            Some(id) => match self.0[id] {
+                Node::Directory { .. } => Some(id),
+                Node::File(_) => None,
+            },
+            None => Some(self.insert(
+                dir,
+                name,
+                Node::Directory {
+                    name: name.to_owned(),
+                    parent: Some(dir),
+                    children: HashMap::new(),
+                },
+            )),
+        }
+    }
 
-                    continue;
+    // Records a listed file; a later listing with a different size wins.
+    fn file(&mut self, dir: NodeId, name: &str, size: u32) -> Option<Warning> {
+        let Some(id) = self.child(dir, name) else {
+            self.insert(dir, name, Node::File(size));
+            return None;
+        };
+        let path = self.path(dir, name);
+
+        match &mut self.0[id] {
+            Node::File(previous) if *previous == size => None,
+            Node::File(previous) => Some(Warning::SizeMismatch {
+                path,
+                previous: std::mem::replace(previous, size),
+                listed: size,
+            }),
+            Node::Directory { .. } => Some(Warning::DirectoryListedAsFile { path }),
+        }
+    }
+
+    // Sizes of every directory from a single post-order walk, so the root comes last.
+    fn directory_sizes(&self) -> Vec<(NodeId, u32)> {
+        let mut sizes = vec![0; self.0.len()];
+        let mut directories = vec![];
+        let mut stack = vec![(ROOT, false)];
+
+        while let Some((node, visited)) = stack.pop() {
+            match &self.0[node] {
+                Node::File(size) => sizes[node] = *size,
+                Node::Directory { children, .. } if !visited => {
+                    stack.push((node, true));
+                    stack.extend(children.values().map(|child| (*child, false)));
+                }
+                Node::Directory { children, .. } => {
+                    sizes[node] = children.values().map(|child| sizes[*child]).sum();
+                    directories.push((node, sizes[node]));
                 }
+            }
+        }
+
+        directories
+    }
+
+    // Replays the transcript in one pass. Repeated listings merge into the same nodes and
+    // anything contradicting what was already seen is reported rather than trusted. After
+    // a failed cd, `lost` counts how far below `cwd` the session wandered; nothing listed
+    // down there can be placed, so it is skipped until the session climbs back.
+    fn build(terminal: &Terminal) -> (Self, Vec<Warning>) {
+        let mut tree = FsTree::new();
+        let mut warnings = vec![];
+        let mut cwd = ROOT;
+        let mut lost = 0;
 
-                match dir_sizes.contains_key(&from) {
-                    true => {
-                        if let Some(size_tally) = dir_sizes.get_mut(&from) {
-                            *size_tally += *edge_weight;
+        for line in &terminal.0 {
+            match line {
+                TerminalLine::Command(Command::ChangeDirectory(dir)) => match dir {
+                    Directory::Back if lost > 0 => lost -= 1,
+                    Directory::Back => match tree.parent(cwd) {
+                        Some(parent) => cwd = parent,
+                        None => warnings.push(Warning::BackFromRoot),
+                    },
+                    Directory::Folder(dir) if dir == "/" => (cwd, lost) = (ROOT, 0),
+                    Directory::Folder(_) if lost > 0 => lost += 1,
+                    Directory::Folder(dir) => match tree.directory(cwd, dir) {
+                        Some(child) => cwd = child,
+                        None => {
+                            warnings.push(Warning::NotADirectory {
+                                path: tree.path(cwd, dir),
+                            });
+                            lost = 1;
+                        }
+                    },
+                },
+                TerminalLine::Command(Command::List) => (),
+                TerminalLine::Output(_) if lost > 0 => (),
+                TerminalLine::Output(output) => {
+                    for out in output {
+                        match out {
+                            Output::Directory(Directory::Folder(dir)) => {
+                                if tree.directory(cwd, dir).is_none() {
+                                    warnings.push(Warning::FileListedAsDirectory {
+                                        path: tree.path(cwd, dir),
+                                    });
+                                }
+                            }
+                            Output::Directory(Directory::Back) => unreachable!(),
+                            Output::File(filename, filesize) => {
+                                warnings.extend(tree.file(cwd, filename, *filesize));
+                            }
                         }
-                    }
-                    false => {
-                        dir_sizes.insert(from, *edge_weight);
                     }
                 }
             }
         }
+
+        (tree, warnings)
     }
+}
 
-    dir_sizes
+fn calculate_dir_size(input: &str) -> (Vec<(NodeId, u32)>, Vec<Warning>) {
+    let terminal = Terminal::parse_raw_lines(input);
+    let (tree, warnings) = FsTree::build(&terminal);
+
+    (tree.directory_sizes(), warnings)
 }
 
-fn part_1(input: &str) -> u32 {
-    calculate_dir_size(input)
+fn part_1(folders: &[(NodeId, u32)]) -> u32 {
+    folders
         .iter()
-        .filter(|dir| dir.1 <= &DISK_SIZE_THRESHOLD)
-        .map(|dir| dir.1)
+        .filter(|(_, size)| size <= &DISK_SIZE_THRESHOLD)
+        .map(|(_, size)| size)
         .sum::<u32>()
 }
 
-fn part_2(input: &str) -> u32 {
-    let folders = calculate_dir_size(input);
+fn part_2(folders: &[(NodeId, u32)]) -> u32 {
+    let (_, root_size) = folders.iter().find(|(dir, _)| *dir == ROOT).unwrap();
+    let unused_space = TOTAL_DISK_SIZE.saturating_sub(*root_size);
+    let space_needed_for_update = SPACE_NEEDED.saturating_sub(unused_space);
 
-    let root_size = folders.get("/").unwrap();
-    let unused_space = TOTAL_DISK_SIZE - root_size;
-    let space_needed_for_update = SPACE_NEEDED - unused_space;
+    // Nothing has to be deleted when the disk already has room for the update.
+    if space_needed_for_update == 0 {
+        return 0;
+    }
 
+    // The root always qualifies, since it holds everything that takes up space.
     folders
-        .into_iter()
+        .iter()
         .filter(|(_, size)| size >= &space_needed_for_update)
-        .map(|(_, size)| size)
+        .map(|(_, size)| *size)
         .min()
         .unwrap()
 }
 
 pub(crate) fn run() {
-    let input = include_str!("../input/07.txt");
+    let (folders, _) = calculate_dir_size(include_str!("../input/07.txt"));
 
     println!("Day 07");
-    println!("\tPart 1: {}", part_1(input));
-    println!("\tPart 2: {}", part_2(input));
+    println!("\tPart 1: {}", part_1(&folders));
+    println!("\tPart 2: {}", part_2(&folders));
+}
+
+pub(crate) fn run_with(args: &Args) {
+    let input = args.input(include_str!("../input/07.txt"));
+
+    match args.mode() {
+        Some(mode) => panic!("unknown day 07 mode: {mode}"),
+        None => {
+            let (folders, warnings) = calculate_dir_size(&input);
+
+            println!("Day 07");
+
+            if !warnings.is_empty() {
+                println!("\tWarnings:");
+                for warning in &warnings {
+                    println!("\t\t{warning}");
+                }
+            }
+
+            println!("\tPart 1: {}", part_1(&folders));
+            println!("\tPart 2: {}", part_2(&folders));
+        }
+    }
 }
@@ -13,6 +13,8 @@ impl From<char> for Height {
 
 struct Tree(Height, Coordinates);
 
+type Partition<'a> = (Vec<&'a Tree>, Vec<&'a Tree>);
+
 impl Tree {
     const fn height(&self) -> i32 {
         self.0 .0
@@ -56,10 +58,7 @@ impl Tree {
         self.height() < another.height()
     }
 
-    fn grid_like_partition<'a>(
-        &'a self,
-        trees_map: &'a [Self],
-    ) -> ((Vec<&Self>, Vec<&Self>), (Vec<&Self>, Vec<&Self>)) {
+    fn grid_like_partition<'a>(&'a self, trees_map: &'a [Self]) -> (Partition<'a>, Partition<'a>) {
         let (same_row, same_col): (Vec<&Self>, Vec<&Self>) = trees_map
             .iter()
             .filter(|tree| self.is_adjacent_to(tree))
@@ -126,9 +126,7 @@ struct CrtLine(Vec<char>);
 
 impl Display for CrtLine {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0
-            .iter()
-            .fold(Ok(()), |acc, pixel| acc.and_then(|_| write!(f, "{pixel}")))
+        self.0.iter().try_for_each(|pixel| write!(f, "{pixel}"))
     }
 }
 
@@ -142,9 +140,7 @@ struct Crt(Vec<CrtLine>);
 
 impl Display for Crt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0
-            .iter()
-            .fold(Ok(()), |acc, line| acc.and_then(|_| writeln!(f, "{line}")))
+        self.0.iter().try_for_each(|line| writeln!(f, "{line}"))
     }
 }
 
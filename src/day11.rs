@@ -144,7 +144,7 @@ impl From<&str> for Test {
 
 impl Test {
     fn eval(&self, worry_level: WorryLevel) -> MonkeyID {
-        if let true = self.cond.divisible(worry_level) {
+        if self.cond.divisible(worry_level) {
             if let TestBranch::True(monkey_id) = &self.branch_true {
                 return *monkey_id;
             }
@@ -325,8 +325,8 @@ fn monkey_business(input: &str, rounds: u32, managed_level: WorryLevelManagement
     top_inspections
         .iter()
         .take(2)
-        .map(|(_, count)| count)
-        .fold(1, |acc, &count| acc * count)
+        .map(|(_, count)| *count)
+        .product()
 }
 
 fn part_1(input: &str) -> u128 {
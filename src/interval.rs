@@ -0,0 +1,202 @@
+use std::fmt::Display;
+
+pub(crate) trait Step: Copy + Ord {
+    fn succ(self) -> Option<Self>;
+    fn pred(self) -> Option<Self>;
+    fn distance(low: Self, high: Self) -> u64;
+}
+
+macro_rules! impl_step {
+    ($($t:ty),*) => {
+        $(
+            impl Step for $t {
+                fn succ(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+
+                fn pred(self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+
+                fn distance(low: Self, high: Self) -> u64 {
+                    (high as i128 - low as i128) as u64
+                }
+            }
+        )*
+    };
+}
+
+impl_step!(u8, u16, u32, u64, usize, i32, i64);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct Interval<T> {
+    start: T,
+    end: T,
+}
+
+impl<T: Ord + Copy> Interval<T> {
+    pub(crate) fn new(start: T, end: T) -> Self {
+        assert!(start <= end, "interval start must not exceed its end");
+
+        Self { start, end }
+    }
+
+    pub(crate) fn start(&self) -> T {
+        self.start
+    }
+
+    pub(crate) fn end(&self) -> T {
+        self.end
+    }
+
+    pub(crate) fn contains(&self, value: T) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    pub(crate) fn contains_interval(&self, other: &Self) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub(crate) fn overlaps(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub(crate) fn intersection(&self, other: &Self) -> Option<Self> {
+        self.overlaps(other)
+            .then(|| Self::new(self.start.max(other.start), self.end.min(other.end)))
+    }
+}
+
+impl<T: Step> Interval<T> {
+    // Wider than the bounds, since `0..=u64::MAX` holds 2^64 sections.
+    pub(crate) fn len(&self) -> u128 {
+        T::distance(self.start, self.end) as u128 + 1
+    }
+
+    fn touches(&self, other: &Self) -> bool {
+        self.overlaps(other)
+            || self.end.succ() == Some(other.start)
+            || other.end.succ() == Some(self.start)
+    }
+
+    // The parts of `self` not covered by `other`: up to one piece on each side.
+    pub(crate) fn difference(&self, other: &Self) -> (Option<Self>, Option<Self>) {
+        if !self.overlaps(other) {
+            return (Some(*self), None);
+        }
+
+        let before = (self.start < other.start)
+            .then(|| other.start.pred().map(|end| Self::new(self.start, end)))
+            .flatten();
+        let after = (other.end < self.end)
+            .then(|| other.end.succ().map(|start| Self::new(start, self.end)))
+            .flatten();
+
+        (before, after)
+    }
+}
+
+impl<T: Display> Display for Interval<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+// Sorted, disjoint and non-adjacent intervals.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct IntervalSet<T>(Vec<Interval<T>>);
+
+impl<T: Step> IntervalSet<T> {
+    pub(crate) fn intervals(&self) -> impl Iterator<Item = &Interval<T>> {
+        self.0.iter()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn len(&self) -> u128 {
+        self.0.iter().map(Interval::len).sum()
+    }
+
+    pub(crate) fn difference(&self, other: &Self) -> Self {
+        let mut difference = vec![];
+
+        for interval in &self.0 {
+            let mut rest = Some(*interval);
+
+            for cut in other.0.iter().filter(|cut| cut.overlaps(interval)) {
+                let Some(remaining) = rest else {
+                    break;
+                };
+
+                let (before, after) = remaining.difference(cut);
+                difference.extend(before);
+                rest = after;
+            }
+
+            difference.extend(rest);
+        }
+
+        Self(difference)
+    }
+
+    fn normalise(&mut self) {
+        self.0.sort();
+
+        let mut merged: Vec<Interval<T>> = Vec::with_capacity(self.0.len());
+
+        for interval in self.0.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.touches(&interval) => last.end = last.end.max(interval.end),
+                _ => merged.push(interval),
+            }
+        }
+
+        self.0 = merged;
+    }
+}
+
+// Set algebra no day uses yet, kept so other range-based puzzles can reuse it.
+#[allow(dead_code)]
+impl<T: Step> IntervalSet<T> {
+    pub(crate) fn contains(&self, value: T) -> bool {
+        let idx = self.0.partition_point(|interval| interval.end < value);
+
+        self.0
+            .get(idx)
+            .is_some_and(|interval| interval.contains(value))
+    }
+
+    pub(crate) fn union(&self, other: &Self) -> Self {
+        self.0.iter().chain(&other.0).copied().collect()
+    }
+
+    pub(crate) fn intersection(&self, other: &Self) -> Self {
+        let (mut i, mut j) = (0, 0);
+        let mut intersection = vec![];
+
+        while i < self.0.len() && j < other.0.len() {
+            let (a, b) = (&self.0[i], &other.0[j]);
+
+            if let Some(common) = a.intersection(b) {
+                intersection.push(common);
+            }
+
+            match a.end.cmp(&b.end) {
+                std::cmp::Ordering::Less => i += 1,
+                _ => j += 1,
+            }
+        }
+
+        Self(intersection)
+    }
+}
+
+impl<T: Step> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(intervals: I) -> Self {
+        let mut set = Self(intervals.into_iter().collect());
+        set.normalise();
+        set
+    }
+}
@@ -1,3 +1,4 @@
+mod cli;
 mod day01;
 mod day02;
 mod day03;
@@ -9,17 +10,43 @@ mod day08;
 mod day09;
 mod day10;
 mod day11;
+mod interval;
 
 fn main() {
-    day01::run();
-    day02::run();
-    day03::run();
-    day04::run();
-    day05::run();
-    day06::run();
-    day07::run();
-    day08::run();
-    day09::run();
-    day10::run();
-    day11::run();
+    let mut raw_args = std::env::args().skip(1);
+
+    let day = match raw_args.next() {
+        Some(day) => day,
+        None => {
+            day01::run();
+            day02::run();
+            day03::run();
+            day04::run();
+            day05::run();
+            day06::run();
+            day07::run();
+            day08::run();
+            day09::run();
+            day10::run();
+            day11::run();
+            return;
+        }
+    };
+
+    let args = cli::Args::parse(raw_args);
+
+    match day.trim_start_matches('0') {
+        "1" => day01::run_with(&args),
+        "2" => day02::run_with(&args),
+        "3" => day03::run_with(&args),
+        "4" => day04::run_with(&args),
+        "5" => day05::run_with(&args),
+        "6" => day06::run_with(&args),
+        "7" => day07::run_with(&args),
+        "8" => day08::run(),
+        "9" => day09::run(),
+        "10" => day10::run(),
+        "11" => day11::run(),
+        _ => panic!("unknown day: {day}"),
+    }
 }